@@ -1,36 +1,600 @@
 use std::str::FromStr;
-use chess::{Board, BoardStatus, ChessMove, MoveGen, Piece, Square};
+use crate::state::Promotion;
+use chess::{
+    BitBoard, Board, BoardStatus, ChessMove, Color, File, MoveGen, Piece, Rank, Square, EMPTY,
+};
+
+// The standard starting position, used wherever a game doesn't set its own
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// Halfmove clock value from which either player may claim a draw under the fifty-move rule
+pub const FIFTY_MOVE_PLIES: u32 = 100;
+// Halfmove clock value at which the seventy-five-move rule draws the game without a claim
+pub const SEVENTY_FIVE_MOVE_PLIES: u32 = 150;
+
+// a1, c1, ..., h8 - used to tell which colour of square a bishop is bound to
+const DARK_SQUARES: BitBoard = BitBoard(0x55AA_55AA_55AA_55AA);
+
+// How a move left the game: the chess crate's BoardStatus, plus the material draws it misses
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+}
+
+// A legal move played out: the resulting position, how it left the game, and what it took
+pub struct ValidatedMove {
+    pub fen: String,
+    pub outcome: MoveOutcome,
+    pub captured: Option<Piece>,
+    pub san: String,
+    pub halfmove_clock: u16, // The new FEN's move counters
+    pub fullmove_number: u16,
+}
+
+// Everything the queries derive from a position, computed from a single parsed board
+pub struct PositionSummary {
+    pub in_check: bool,
+    pub legal_move_count: u32,
+    pub is_terminal: bool,
+}
+
+pub fn parse_board(fen: &str) -> Result<Board, &'static str> {
+    Board::from_str(fen).map_err(|_| "Invalid FEN")
+}
+
+pub fn summarize(board: &Board) -> PositionSummary {
+    // One move generation pass: no legal moves means checkmate or stalemate
+    let legal_move_count = MoveGen::new_legal(board).len() as u32;
+    PositionSummary {
+        in_check: *board.checkers() != EMPTY,
+        legal_move_count,
+        is_terminal: legal_move_count == 0,
+    }
+}
+
+// Lowercase promotion letter used in coordinate notation, e.g. the "q" in "e7e8q"
+pub fn promotion_letter(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Queen => "q",
+        Piece::Rook => "r",
+        Piece::Bishop => "b",
+        Piece::Knight => "n",
+        Piece::Pawn => "p",
+        Piece::King => "k",
+    }
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.get_file().to_index() as u8) as char
+}
+
+fn rank_char(square: Square) -> char {
+    (b'1' + square.get_rank().to_index() as u8) as char
+}
+
+// Strictly parses a square name such as "e4"; the chess crate's parser ignores trailing input
+pub fn parse_square(text: &str) -> Option<Square> {
+    match text.as_bytes() {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(Square::make_square(
+            Rank::from_index((rank - b'1') as usize),
+            File::from_index((file - b'a') as usize),
+        )),
+        _ => None,
+    }
+}
+
+// Parses a move in the coordinate notation stored in a game's history, e.g. "e2e4" or "e7e8q"
+pub fn parse_coordinate_move(text: &str) -> Result<ChessMove, &'static str> {
+    if !text.is_ascii() || text.len() < 4 {
+        return Err("Invalid move");
+    }
+    let from = parse_square(&text[0..2]).ok_or("Invalid from-square")?;
+    let to = parse_square(&text[2..4]).ok_or("Invalid to-square")?;
+    let promotion = match &text[4..] {
+        "" => None,
+        "q" => Some(Piece::Queen),
+        "r" => Some(Piece::Rook),
+        "b" => Some(Piece::Bishop),
+        "n" => Some(Piece::Knight),
+        _ => return Err("Invalid promotion piece"),
+    };
+    Ok(ChessMove::new(from, to, promotion))
+}
+
+// Standard algebraic notation for a legal move on `board`, e.g. "Nbd7", "exd6", "O-O", "e8=Q#"
+pub fn to_san(board: &Board, chess_move: ChessMove) -> String {
+    let from = chess_move.get_source();
+    let to = chess_move.get_dest();
+    let piece = board.piece_on(from).unwrap_or(Piece::Pawn);
+    let from_file = from.get_file().to_index();
+    let to_file = to.get_file().to_index();
+
+    let mut text = String::new();
+    if piece == Piece::King && from_file.abs_diff(to_file) == 2 {
+        text.push_str(if to_file > from_file { "O-O" } else { "O-O-O" });
+    } else {
+        // A pawn changing file always captures, even onto the empty en passant square
+        let capture =
+            board.piece_on(to).is_some() || (piece == Piece::Pawn && from_file != to_file);
+        if piece == Piece::Pawn {
+            if capture {
+                text.push(file_char(from));
+            }
+        } else {
+            text.push_str(&promotion_letter(piece).to_uppercase());
+            // Another piece of the same kind that could also reach `to` needs telling apart
+            let rivals: Vec<Square> = MoveGen::new_legal(board)
+                .filter(|m| m.get_dest() == to && m.get_source() != from)
+                .map(|m| m.get_source())
+                .filter(|square| board.piece_on(*square) == Some(piece))
+                .collect();
+            if !rivals.is_empty() {
+                let file_unique = rivals.iter().all(|square| square.get_file() != from.get_file());
+                let rank_unique = rivals.iter().all(|square| square.get_rank() != from.get_rank());
+                if file_unique {
+                    text.push(file_char(from));
+                } else if rank_unique {
+                    text.push(rank_char(from));
+                } else {
+                    text.push(file_char(from));
+                    text.push(rank_char(from));
+                }
+            }
+        }
+        if capture {
+            text.push('x');
+        }
+        text.push(file_char(to));
+        text.push(rank_char(to));
+        if let Some(promotion) = chess_move.get_promotion() {
+            text.push('=');
+            text.push_str(&promotion_letter(promotion).to_uppercase());
+        }
+    }
+
+    let after = board.make_move_new(chess_move);
+    if after.status() == BoardStatus::Checkmate {
+        text.push('#');
+    } else if *after.checkers() != EMPTY {
+        text.push('+');
+    }
+    text
+}
+
+// Replays a coordinate-notation history from `start_fen` and returns each move in SAN
+pub fn san_moves(start_fen: &str, moves: &[String]) -> Result<Vec<String>, &'static str> {
+    let mut board = parse_board(start_fen)?;
+    let mut sans = Vec::with_capacity(moves.len());
+    for text in moves {
+        let chess_move = parse_coordinate_move(text)?;
+        if !board.legal(chess_move) {
+            return Err("Illegal move in history");
+        }
+        sans.push(to_san(&board, chess_move));
+        board = board.make_move_new(chess_move);
+    }
+    Ok(sans)
+}
+
+// No position has more than 218 legal moves, so the full list is always small enough to return
+pub fn legal_moves(board: &Board) -> Vec<ChessMove> {
+    MoveGen::new_legal(board).collect()
+}
+
+// Every legal move in the position, in the same notation `GameState.moves` records
+pub fn legal_coordinate_moves(fen: &str) -> Result<Vec<String>, &'static str> {
+    let board = parse_board(fen)?;
+    Ok(MoveGen::new_legal(&board)
+        .map(|m| {
+            let promotion = m.get_promotion().map(promotion_letter).unwrap_or("");
+            format!("{}{}{}", m.get_source(), m.get_dest(), promotion)
+        })
+        .collect())
+}
+
+// The position as 8 ranks of 8 squares, rank 8 first and the a-file first within each rank, as
+// drawn from White's side. Occupied squares read colour then piece, e.g. "wP" or "bK"; empty is ""
+pub fn board_grid(fen: &str) -> Result<Vec<Vec<String>>, &'static str> {
+    let board = parse_board(fen)?;
+    Ok((0..8)
+        .rev()
+        .map(|rank| {
+            (0..8)
+                .map(|file| {
+                    let square =
+                        Square::make_square(Rank::from_index(rank), File::from_index(file));
+                    match (board.color_on(square), board.piece_on(square)) {
+                        (Some(color), Some(piece)) => {
+                            let side = if color == Color::White { 'w' } else { 'b' };
+                            let letter = promotion_letter(piece).to_uppercase();
+                            format!("{side}{letter}")
+                        }
+                        _ => String::new(),
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+// Neither side can ever mate: K v K, K+minor v K, or only bishops that all stand on one colour
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let heavy = board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    if heavy != EMPTY {
+        return false;
+    }
+    let knights = *board.pieces(Piece::Knight);
+    let bishops = *board.pieces(Piece::Bishop);
+    if (knights | bishops).popcnt() <= 1 {
+        return true;
+    }
+    knights == EMPTY && ((bishops & DARK_SQUARES) == EMPTY || (bishops & !DARK_SQUARES) == EMPTY)
+}
+
+// White's material minus Black's, counting P=1, N=B=3, R=5, Q=9; 0 for an unreadable FEN
+pub fn material_balance(fen: &str) -> i32 {
+    let board = match parse_board(fen) {
+        Ok(board) => board,
+        Err(_) => return 0,
+    };
+    let values = [
+        (Piece::Pawn, 1),
+        (Piece::Knight, 3),
+        (Piece::Bishop, 3),
+        (Piece::Rook, 5),
+        (Piece::Queen, 9),
+    ];
+    values
+        .iter()
+        .map(|(piece, value)| {
+            let white = (board.pieces(*piece) & board.color_combined(Color::White)).popcnt();
+            let black = (board.pieces(*piece) & board.color_combined(Color::Black)).popcnt();
+            (white as i32 - black as i32) * value
+        })
+        .sum()
+}
+
+// Returns the (halfmove clock, fullmove number) fields of a FEN
+pub fn parse_fen_counters(fen: &str) -> Result<(u16, u16), &'static str> {
+    let mut fields = fen.split_whitespace().skip(4);
+    let halfmove = fields.next().and_then(|f| f.parse().ok()).ok_or("Invalid halfmove clock")?;
+    let fullmove = fields.next().and_then(|f| f.parse().ok()).ok_or("Invalid fullmove number")?;
+    Ok((halfmove, fullmove))
+}
+
+// As parse_fen_counters, but a FEN without usable counters starts the count afresh
+fn fen_counters(fen: &str) -> (u32, u32) {
+    match parse_fen_counters(fen) {
+        Ok((halfmove, fullmove)) => (u32::from(halfmove), u32::from(fullmove)),
+        Err(_) => (0, 1),
+    }
+}
+
+// The FEN without its move counters, which is what has to match for a position to repeat
+pub fn position_key(fen: &str) -> String {
+    fen.split_whitespace().take(4).collect::<Vec<&str>>().join(" ")
+}
+
+// Chess960 starting position `number` (0-959, wrapping), numbered the standard way so 518 is the
+// usual setup. The chess crate only knows castling with the king on e1 and rooks in the corners,
+// which is also exactly how Chess960 castles from that setup, so only those rights are granted.
+pub fn chess960_fen(number: u16) -> String {
+    let mut n = (number % 960) as usize;
+    let mut back_rank = [None; 8];
+    // Light-squared bishop on b, d, f or h, then dark-squared bishop on a, c, e or g
+    back_rank[2 * (n % 4) + 1] = Some('b');
+    n /= 4;
+    back_rank[2 * (n % 4)] = Some('b');
+    n /= 4;
+    let place = |back_rank: &mut [Option<char>; 8], nth_empty: usize, piece: char| {
+        let file = (0..8).filter(|&f| back_rank[f].is_none()).nth(nth_empty).unwrap_or(0);
+        back_rank[file] = Some(piece);
+    };
+    place(&mut back_rank, n % 6, 'q');
+    n /= 6;
+    // The ten ways of choosing two of the five squares left for the knights
+    let knights = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+    let (first, second) = knights[n];
+    place(&mut back_rank, first, 'n');
+    // One fewer empty square comes before the second knight once the first is placed
+    place(&mut back_rank, second - 1, 'n');
+    // Rook, king, rook on whatever is left, so the king always sits between the rooks
+    for piece in ['r', 'k', 'r'] {
+        place(&mut back_rank, 0, piece);
+    }
+
+    let black: String = back_rank.iter().map(|piece| piece.unwrap_or('r')).collect();
+    let white = black.to_uppercase();
+    let mut castling = String::new();
+    if back_rank[4] == Some('k') {
+        if back_rank[7] == Some('r') {
+            castling.push('K');
+        }
+        if back_rank[0] == Some('r') {
+            castling.push('Q');
+        }
+    }
+    castling = format!("{}{}", castling, castling.to_lowercase());
+    if castling.is_empty() {
+        castling.push('-');
+    }
+    format!("{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w {castling} - 0 1")
+}
+
+// Checks a custom starting position and returns it with explicit move counters
+pub fn validate_start_position(fen: &str) -> Result<String, &'static str> {
+    let board = parse_board(fen)?;
+    if board.status() != BoardStatus::Ongoing {
+        return Err("Starting position is already checkmate or stalemate");
+    }
+    let (halfmove, fullmove) = fen_counters(fen);
+    let position = board.to_string();
+    let fields: Vec<&str> = position.split_whitespace().take(4).collect();
+    Ok(format!("{} {} {}", fields.join(" "), halfmove, fullmove.max(1)))
+}
+
+// Whether the side to move is in check; an unparseable FEN counts as not in check
+pub fn in_check(fen: &str) -> bool {
+    parse_board(fen).map(|board| *board.checkers() != EMPTY).unwrap_or(false)
+}
+
+pub fn white_to_move(fen: &str) -> bool {
+    fen.split_whitespace().nth(1) != Some("b")
+}
+
+pub fn halfmove_clock(fen: &str) -> u32 {
+    fen_counters(fen).0
+}
+
+pub fn fullmove_number(fen: &str) -> u32 {
+    fen_counters(fen).1
+}
 
 pub fn validate_move(
     fen: &str,
     move_from: &str,
     move_to: &str,
-    promotion: Option<&str>,
-) -> Result<(String, BoardStatus), &'static str> {
-    let board = Board::from_str(fen).map_err(|_| "Invalid FEN")?; // Should never happen because of this validation
-
-    let from = Square::from_str(move_from).map_err(|_| "Invalid from-square")?;
-    let to = Square::from_str(move_to).map_err(|_| "Invalid to-square")?;
-
-    let promo = match promotion {
-        Some("q") | Some("Q") => Some(Piece::Queen),
-        Some("r") | Some("R") => Some(Piece::Rook),
-        Some("b") | Some("B") => Some(Piece::Bishop),
-        Some("n") | Some("N") => Some(Piece::Knight),
-        Some("") | None => None,
-        Some(_) => return Err("Invalid promotion piece"),
-    };
+    promotion: Option<Promotion>,
+) -> Result<ValidatedMove, &'static str> {
+    let board = parse_board(fen)?; // Should never happen because of this validation
+
+    let from = parse_square(move_from).ok_or("Invalid from-square")?;
+    let to = parse_square(move_to).ok_or("Invalid to-square")?;
+    if from == to {
+        return Err("From and to squares are the same");
+    }
 
+    // Say what's wrong with the promotion instead of letting the move fail as merely illegal
+    let last_rank = if board.side_to_move() == Color::White { Rank::Eighth } else { Rank::First };
+    let promoting = board.piece_on(from) == Some(Piece::Pawn)
+        && board.color_on(from) == Some(board.side_to_move())
+        && to.get_rank() == last_rank;
+    match (promoting, promotion) {
+        (true, None) => return Err("Promotion required"),
+        (false, Some(_)) => return Err("Unexpected promotion"),
+        _ => {}
+    }
+
+    let promo = promotion.map(|promotion| match promotion {
+        Promotion::Queen => Piece::Queen,
+        Promotion::Rook => Piece::Rook,
+        Promotion::Bishop => Piece::Bishop,
+        Promotion::Knight => Piece::Knight,
+    });
+
+    // Castling is the king's two-square move, e.g. "e1" to "g1", which is how the chess crate
+    // encodes it too, so it needs no special casing here
     let candidate_move = ChessMove::new(from, to, promo);
 
     let mut legal_moves = MoveGen::new_legal(&board);
 
     if legal_moves.any(|m| m == candidate_move) {
+        // The chess crate doesn't track move counters (it always writes "0 1"), so carry them over
+        let (halfmove, fullmove) = parse_fen_counters(fen).unwrap_or((0, 1));
+        let resets_clock = board.piece_on(from) == Some(Piece::Pawn) || board.piece_on(to).is_some();
+        let halfmove = if resets_clock { 0 } else { halfmove.saturating_add(1) };
+        let fullmove = if board.side_to_move() == Color::Black {
+            fullmove.saturating_add(1)
+        } else {
+            fullmove
+        };
+        // En passant is the one capture whose victim isn't on the destination square
+        let en_passant = board.piece_on(from) == Some(Piece::Pawn)
+            && from.get_file() != to.get_file()
+            && board.piece_on(to).is_none();
+        let captured = if en_passant { Some(Piece::Pawn) } else { board.piece_on(to) };
+
+        let san = to_san(&board, candidate_move);
+
         // Apply the move and return new FEN
         let new_board = board.make_move_new(candidate_move);
-        let board_status = new_board.status();
-        Ok((new_board.to_string(), board_status))
+        let outcome = match new_board.status() {
+            BoardStatus::Checkmate => MoveOutcome::Checkmate,
+            BoardStatus::Stalemate => MoveOutcome::Stalemate,
+            BoardStatus::Ongoing if is_insufficient_material(&new_board) => {
+                MoveOutcome::InsufficientMaterial
+            }
+            BoardStatus::Ongoing => MoveOutcome::Ongoing,
+        };
+        let position = new_board.to_string();
+        let fields: Vec<&str> = position.split_whitespace().take(4).collect();
+        let new_fen = format!("{} {} {}", fields.join(" "), halfmove, fullmove);
+        Ok(ValidatedMove {
+            fen: new_fen,
+            outcome,
+            captured,
+            san,
+            halfmove_clock: halfmove,
+            fullmove_number: fullmove,
+        })
     } else {
         Err("Illegal move")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_moves_count_towards_the_fifty_move_rule() {
+        let played = validate_move("4k3/8/8/8/8/8/8/R3K3 w - - 99 80", "a1", "a2", None).unwrap();
+        assert_eq!(u32::from(played.halfmove_clock), FIFTY_MOVE_PLIES);
+        assert_eq!(played.fen, "4k3/8/8/8/8/8/R7/4K3 b - - 100 80");
+        assert_eq!(halfmove_clock(&played.fen), FIFTY_MOVE_PLIES);
+    }
+
+    #[test]
+    fn pawn_moves_and_captures_reset_the_halfmove_clock() {
+        let pawn = validate_move("4k3/8/8/8/8/8/P7/4K3 w - - 99 80", "a2", "a3", None).unwrap();
+        assert_eq!(pawn.halfmove_clock, 0);
+        let capture = validate_move("4k3/8/8/8/8/8/r7/R3K3 w - - 99 80", "a1", "a2", None).unwrap();
+        assert_eq!(capture.halfmove_clock, 0);
+        assert_eq!(capture.captured, Some(Piece::Rook));
+    }
+
+    fn insufficient(fen: &str) -> bool {
+        is_insufficient_material(&parse_board(fen).unwrap())
+    }
+
+    #[test]
+    fn lone_kings_and_single_minors_cannot_mate() {
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn bishops_on_one_colour_cannot_mate() {
+        // c1 and f8 are both dark squares
+        assert!(insufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn bishops_on_both_colours_can_still_mate() {
+        // c1 is dark, c8 is light
+        assert!(!insufficient("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"));
+    }
+
+    fn san(fen: &str, coordinate: &str) -> String {
+        to_san(&parse_board(fen).unwrap(), parse_coordinate_move(coordinate).unwrap())
+    }
+
+    #[test]
+    fn castling_is_written_as_o_o() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1g1"), "O-O");
+        assert_eq!(san(fen, "e1c1"), "O-O-O");
+    }
+
+    #[test]
+    fn promotions_and_en_passant_name_the_move() {
+        assert_eq!(san("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q"), "e8=Q");
+        assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn ambiguous_moves_are_disambiguated() {
+        // Both knights reach d2, but only one stands on the b-file
+        assert_eq!(san("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1d2"), "Nbd2");
+        // Both rooks are on the a-file, so the rank tells them apart
+        assert_eq!(san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+    }
+
+    #[test]
+    fn checks_and_mates_are_marked() {
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn san_moves_replays_the_history() {
+        let history = ["e2e4", "e7e5", "g1f3"].map(String::from);
+        assert_eq!(san_moves(STARTING_FEN, &history).unwrap(), ["e4", "e5", "Nf3"]);
+        assert!(san_moves(STARTING_FEN, &["e2e5".to_string()]).is_err());
+    }
+
+    #[test]
+    fn fen_counters_are_read_from_the_last_two_fields() {
+        assert_eq!(parse_fen_counters(STARTING_FEN), Ok((0, 1)));
+        assert_eq!(parse_fen_counters("4k3/8/8/8/8/8/8/4K3 b - - 12 34"), Ok((12, 34)));
+    }
+
+    #[test]
+    fn missing_or_malformed_fen_counters_are_rejected() {
+        let position = "4k3/8/8/8/8/8/8/4K3 w - -";
+        assert_eq!(parse_fen_counters(position), Err("Invalid halfmove clock"));
+        assert_eq!(parse_fen_counters(&format!("{position} 3")), Err("Invalid fullmove number"));
+        assert_eq!(parse_fen_counters(&format!("{position} x 1")), Err("Invalid halfmove clock"));
+        assert_eq!(parse_fen_counters(&format!("{position} -1 1")), Err("Invalid halfmove clock"));
+        assert_eq!(parse_fen_counters(&format!("{position} 0 y")), Err("Invalid fullmove number"));
+        // The lenient readers start the count afresh instead
+        assert_eq!(halfmove_clock(position), 0);
+        assert_eq!(fullmove_number(&format!("{position} x 1")), 1);
+    }
+
+    #[test]
+    fn board_grid_draws_the_starting_position() {
+        assert!(parse_board(STARTING_FEN).is_ok());
+        let grid = board_grid(STARTING_FEN).unwrap();
+        assert_eq!(grid.len(), 8);
+        assert_eq!(grid[0], ["bR", "bN", "bB", "bQ", "bK", "bB", "bN", "bR"]);
+        assert!(grid[1].iter().all(|square| square == "bP"));
+        assert!(grid[2..6].iter().flatten().all(|square| square.is_empty()));
+        assert!(grid[6].iter().all(|square| square == "wP"));
+        assert_eq!(grid[7], ["wR", "wN", "wB", "wQ", "wK", "wB", "wN", "wR"]);
+    }
+
+    #[test]
+    fn board_grid_follows_a_capture() {
+        let mut fen = STARTING_FEN.to_string();
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5")] {
+            fen = validate_move(&fen, from, to, None).unwrap().fen;
+        }
+        let grid = board_grid(&fen).unwrap();
+        assert_eq!(grid[3][3], "wP"); // d5 now holds the capturing pawn
+        assert_eq!(grid[4][4], ""); // e4 has been vacated
+        assert_eq!(grid[1][3], ""); // d7 is empty since Black's pawn moved and was taken
+        assert_eq!(grid.iter().flatten().filter(|square| !square.is_empty()).count(), 31);
+    }
+
+    fn back_rank(number: u16) -> String {
+        chess960_fen(number).split('/').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn chess960_numbers_follow_the_standard_table() {
+        assert_eq!(chess960_fen(518), STARTING_FEN);
+        assert_eq!(back_rank(0), "bbqnnrkr");
+        assert_eq!(back_rank(959), "rkrnnqbb");
+        assert_eq!(chess960_fen(960), chess960_fen(0));
+    }
+
+    #[test]
+    fn chess960_positions_are_distinct_and_legal() {
+        let mut seen = std::collections::BTreeSet::new();
+        for number in 0..960 {
+            let rank = back_rank(number);
+            let files = |piece: char| rank.match_indices(piece).map(|(file, _)| file).collect();
+            let bishops: Vec<usize> = files('b');
+            let rooks: Vec<usize> = files('r');
+            let king = rank.find('k').unwrap();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2, "{rank}");
+            assert!(rooks[0] < king && king < rooks[1], "{rank}");
+            assert!(validate_start_position(&chess960_fen(number)).is_ok(), "{rank}");
+            seen.insert(rank);
+        }
+        assert_eq!(seen.len(), 960);
+    }
+
+    #[test]
+    fn chess960_only_castles_from_the_standard_squares() {
+        let castling = |number| chess960_fen(number).split(' ').nth(2).unwrap().to_string();
+        assert_eq!(castling(518), "KQkq");
+        assert_eq!(castling(0), "-");
+    }
+}
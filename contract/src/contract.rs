@@ -1,30 +1,435 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, Response, StdError, StdResult, Uint128,
+    entry_point, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Deps,
+    DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
 };
 
-use crate::chess::validate_move;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryAnswer, QueryMsg};
-use crate::state::{GameState, GameStatus, GAMES, NEXT_GAME_ID};
+use secret_toolkit::snip20;
+
+use crate::chess::{
+    board_grid, chess960_fen, fullmove_number, halfmove_clock, in_check, legal_coordinate_moves,
+    legal_moves, material_balance, parse_board, position_key, promotion_letter, san_moves,
+    summarize, validate_move, validate_start_position, white_to_move, MoveOutcome, ValidatedMove,
+    FIFTY_MOVE_PLIES, SEVENTY_FIVE_MOVE_PLIES, STARTING_FEN,
+};
+use crate::elo::rating_change;
+use crate::error::ContractError;
+use crate::msg::{
+    AdminOutcome, ClockInfo, ContractStats, ExecuteMsg, GameDetail, GameInfo, InstantiateMsg,
+    JoinEligibility, LegalMove, MigrateMsg, Payout, PlayerInfo, QueryAnswer, QueryMsg, ReceiveMsg,
+    SettlementProjection, StatusCount, TimedMove, TurnInfo,
+};
+use crate::state::{
+    Config, ContractVersion, Counters, GameResult, GameState, GameStateV0, GameStateV1, GameStatus,
+    MatchState, PlayerProfile, PlayerStats, Premove, Promotion, TokenContract, ACCRUED_FEES,
+    ACTIVE_GAMES, ARCHIVED_GAMES, CONFIG, CONFIG_V1, CONTRACT_INFO, COUNTERS, GAMES, GAMES_V0,
+    GAMES_V1, MATCHES, NEXT_GAME_ID, NEXT_MATCH_ID, OPEN_GAMES, PLAYERS, PLAYER_GAMES,
+    PLAYER_STATS, SPECTATING, STATE_VERSION, TOTAL_VOLUME,
+};
+
+// Furthest into the future (in blocks) a pending game's join deadline may be set
+pub const MAX_JOIN_WINDOW_BLOCKS: u64 = 100_000;
+// Page sizes for queries returning lists of games
+pub const DEFAULT_LIMIT: u32 = 30;
+pub const MAX_LIMIT: u32 = 100;
+// Most game ids a filtered listing reads for one page, however few of them turn out to match
+pub const MAX_SCAN: usize = 300;
+// Most games that can be exited in a single ResignMany
+pub const MAX_BATCH_SIZE: usize = 20;
+// Most games a single GetGames query may ask for
+pub const MAX_QUERY_BATCH_SIZE: usize = 50;
+// Spectators allowed per game when the instantiator doesn't pick a cap
+pub const DEFAULT_MAX_SPECTATORS: u32 = 50;
+// Rating a player starts with before their first rated game
+pub const DEFAULT_ELO: i32 = 1200;
+pub const MAX_NAME_LENGTH: usize = 32;
+// Padding block size for messages sent to the wager token
+pub const BLOCK_SIZE: usize = 256;
+// Layout version written by this code; bump it alongside a new step in `migrate`
+pub const CURRENT_STATE_VERSION: u16 = 2;
+// Reported by the Version query so clients can tell which features a deployment has
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// Blocks without any activity before a game counts as abandoned (about a week of 6s blocks)
+pub const DEFAULT_ABANDONMENT_BLOCKS: u64 = 100_800;
+// Longest match that can be created
+pub const MAX_BEST_OF: u8 = 9;
+// Plies within which the players can still agree to abort: before Black's first move
+pub const DEFAULT_ABORT_PLIES: u32 = 2;
 
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    // Every standard game starts from this position, so a bad one must never be deployed
+    parse_board(STARTING_FEN).map_err(StdError::generic_err)?;
+    let fee_bps = msg.fee_bps.unwrap_or(0);
+    let referral_bps = msg.referral_bps.unwrap_or(0);
+    let winner_bps = msg.winner_bps.unwrap_or(10_000);
+    let max_spectators = msg.max_spectators.unwrap_or(DEFAULT_MAX_SPECTATORS);
+    if fee_bps > 10_000 || referral_bps > 10_000 || winner_bps > 10_000 {
+        return Err(StdError::generic_err("Basis points cannot exceed 10000"));
+    }
+    let min_wager = msg.min_wager.unwrap_or_default().u128();
+    let max_wager = msg.max_wager.map(|max| max.u128());
+    if matches!(max_wager, Some(max) if max < min_wager) {
+        return Err(StdError::generic_err("Maximum wager is below the minimum"));
+    }
+    let abandonment_blocks = msg.abandonment_blocks.unwrap_or(DEFAULT_ABANDONMENT_BLOCKS);
+    if abandonment_blocks == 0 {
+        return Err(StdError::generic_err(
+            "Abandonment window must be at least one block",
+        ));
+    }
+    if msg.max_plies == Some(0) {
+        return Err(StdError::generic_err("Ply cap must be at least one ply"));
+    }
+    let accrue_fees = msg.accrue_fees.unwrap_or(false);
+    let fee_collector = match msg.fee_collector {
+        Some(_) if accrue_fees => {
+            return Err(StdError::generic_err(
+                "Accrued fees are held by the contract, so no fee_collector can be set",
+            ))
+        }
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None if accrue_fees => env.contract.address.clone(),
+        None => info.sender.clone(),
+    };
+    let owner = match msg.owner {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender,
+    };
+
+    // Ask the token to call Receive on this contract whenever it is sent wagers
+    let mut messages = Vec::new();
+    let wager_token = match msg.wager_token {
+        Some(token) => {
+            let token = TokenContract {
+                address: deps.api.addr_validate(&token.address)?,
+                code_hash: token.code_hash,
+            };
+            messages.push(snip20::register_receive_msg(
+                env.contract.code_hash,
+                None,
+                BLOCK_SIZE,
+                token.code_hash.clone(),
+                token.address.to_string(),
+            )?);
+            Some(token)
+        }
+        None => None,
+    };
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            fee_bps,
+            fee_collector,
+            referral_bps,
+            max_spectators,
+            wager_token,
+            owner,
+            paused: false,
+            min_wager,
+            max_wager,
+            abandonment_blocks,
+            winner_bps,
+            max_active_games_per_player: msg.max_active_games_per_player,
+            archive_finished_games: msg.archive_finished_games.unwrap_or(false),
+            abort_plies: msg.abort_plies.unwrap_or(DEFAULT_ABORT_PLIES),
+            max_plies: msg.max_plies,
+            draw_offer_plies: msg.draw_offer_plies.unwrap_or(0),
+            accrue_fees,
+        },
+    )?;
     NEXT_GAME_ID.save(deps.storage, &0)?;
-    Ok(Response::default())
+    TOTAL_VOLUME.save(deps.storage, &Uint128::zero())?;
+    STATE_VERSION.save(deps.storage, &CURRENT_STATE_VERSION)?;
+    save_contract_version(deps.storage)?;
+    Ok(Response::new().add_messages(messages))
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    let version = STATE_VERSION.may_load(deps.storage)?.unwrap_or(0);
+    if version > CURRENT_STATE_VERSION {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from newer state version {version}"
+        )));
+    }
+    // Each step writes the current layout straight away, so only one of them ever runs
+    match version {
+        0 => migrate_from_v0(deps.storage, deps.api, &env, msg.owner)?,
+        1 => migrate_from_v1(deps.storage)?,
+        _ => {}
+    }
+    STATE_VERSION.save(deps.storage, &CURRENT_STATE_VERSION)?;
+    save_contract_version(deps.storage)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", version.to_string())
+        .add_attribute("to_version", CURRENT_STATE_VERSION.to_string()))
+}
+
+fn save_contract_version(storage: &mut dyn Storage) -> StdResult<()> {
+    CONTRACT_INFO.save(
+        storage,
+        &ContractVersion {
+            contract: CONTRACT_NAME.to_string(),
+            version: CONTRACT_VERSION.to_string(),
+        },
+    )
+}
+
+// Version 0 stored only games and the id counter. Games are rewritten in the current layout in
+// one pass, so a very large version 0 deployment may need a higher gas limit to migrate.
+fn migrate_from_v0(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    owner: Option<String>,
+) -> StdResult<()> {
+    let owner =
+        owner.ok_or_else(|| StdError::generic_err("Migrating from version 0 needs an owner"))?;
+    let owner = api.addr_validate(&owner)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            fee_bps: 0,
+            fee_collector: owner.clone(),
+            referral_bps: 0,
+            max_spectators: DEFAULT_MAX_SPECTATORS,
+            wager_token: None,
+            owner,
+            paused: false,
+            min_wager: 0,
+            max_wager: None,
+            abandonment_blocks: DEFAULT_ABANDONMENT_BLOCKS,
+            winner_bps: 10_000,
+            max_active_games_per_player: None,
+            archive_finished_games: false,
+            abort_plies: DEFAULT_ABORT_PLIES,
+            max_plies: None,
+            draw_offer_plies: 0,
+            accrue_fees: false,
+        },
+    )?;
+    if TOTAL_VOLUME.may_load(storage)?.is_none() {
+        TOTAL_VOLUME.save(storage, &Uint128::zero())?;
+    }
+
+    let legacy_games = GAMES_V0
+        .iter(storage)?
+        .collect::<StdResult<Vec<(u64, GameStateV0)>>>()?;
+    for (game_id, legacy) in legacy_games {
+        // The moves that led here were never stored, so the current position becomes the start
+        // and no plies have been played from it yet. Version 0 games all began from the standard
+        // position but every FEN read "0 1", so the move number is rebuilt from the plies played.
+        let fen = format!("{} 0 {}", position_key(&legacy.fen), 1 + legacy.turn / 2);
+        let mut game = pending_game(game_id, fen, legacy.wager, env);
+        game.white = legacy.white;
+        game.black = legacy.black;
+        game.status = legacy.status;
+        if game.status != GameStatus::Pending {
+            game.started_height = Some(env.block.height);
+        }
+        // Version 0 paid out as soon as a game finished
+        if game.status.is_finished() {
+            game.paid = true;
+            game.winner = winning_player(&game);
+            game.ended_height = Some(env.block.height);
+        }
+        for player in [game.white.clone(), game.black.clone()]
+            .into_iter()
+            .flatten()
+        {
+            index_player_game(storage, &player, game_id)?;
+            if !game.status.is_finished() {
+                enter_active_game(storage, &player, None)?;
+            }
+        }
+        GAMES.insert(storage, &game_id, &game)?;
+        if game.status == GameStatus::Pending {
+            list_open_game(storage, &game)?;
+        }
+        // Unfinished games still hold the stakes of everyone seated
+        let seated = [&game.white, &game.black].into_iter().flatten().count() as u128;
+        update_counters(storage, |counters| {
+            counters.move_game(None, Some(game.status));
+            if !game.status.is_finished() {
+                counters.escrowed = counters.escrowed.saturating_add(game.wager * seated);
+            }
+        })?;
+    }
+    Ok(())
+}
+
+// Version 1 games and config were saved before the fields added since, so both are rewritten in
+// the current layout. The counters, the active game counts and the lobby index came later too,
+// and are rebuilt from the games.
+fn migrate_from_v1(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = CONFIG_V1.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            fee_bps: legacy.fee_bps,
+            fee_collector: legacy.fee_collector,
+            referral_bps: legacy.referral_bps,
+            max_spectators: legacy.max_spectators,
+            wager_token: legacy.wager_token,
+            owner: legacy.owner,
+            paused: legacy.paused,
+            min_wager: legacy.min_wager,
+            max_wager: legacy.max_wager,
+            abandonment_blocks: legacy.abandonment_blocks,
+            winner_bps: 10_000,
+            max_active_games_per_player: None,
+            archive_finished_games: false,
+            abort_plies: DEFAULT_ABORT_PLIES,
+            max_plies: None,
+            draw_offer_plies: 0,
+            accrue_fees: false,
+        },
+    )?;
+
+    let legacy_games = GAMES_V1
+        .iter(storage)?
+        .collect::<StdResult<Vec<(u64, GameStateV1)>>>()?;
+    for (game_id, legacy) in legacy_games {
+        let premove = legacy.premove.map(|premove| Premove {
+            player: premove.player,
+            move_from: premove.move_from,
+            move_to: premove.move_to,
+            // The letter was stored as sent; one that names no piece leaves a premove that is
+            // discarded when its turn comes, as it would have been before
+            promotion: premove
+                .promotion
+                .and_then(|letter| Promotion::from_letter(&letter.to_lowercase())),
+        });
+        let game = GameState {
+            id: legacy.id,
+            fen: legacy.fen,
+            white: legacy.white,
+            black: legacy.black,
+            turn: legacy.turn,
+            status: legacy.status,
+            wager: legacy.wager,
+            join_deadline_height: legacy.join_deadline_height,
+            spectators: legacy.spectators,
+            white_referrer: legacy.white_referrer,
+            black_referrer: legacy.black_referrer,
+            premove,
+            // Blocks weren't recorded per move, so earlier moves all read as the latest one's
+            move_heights: vec![legacy.last_move_height; legacy.moves.len()],
+            moves: legacy.moves,
+            positions: legacy.positions,
+            start_fen: legacy.start_fen,
+            // A standing offer lasts until the next move, as it did in version 1
+            draw_offered_at: legacy.draw_offered_by.as_ref().map(|_| legacy.turn),
+            draw_offered_by: legacy.draw_offered_by,
+            takeback_offered_by: None,
+            abort_offered_by: None,
+            last_move_height: legacy.last_move_height,
+            last_activity_height: legacy.last_activity_height,
+            move_timeout_blocks: legacy.move_timeout_blocks,
+            paid: legacy.paid,
+            invited: legacy.invited,
+            provisional_colors: false,
+            winner: legacy.winner,
+            created_height: legacy.created_height,
+            started_height: legacy.started_height,
+            started_time: legacy.started_time,
+            ended_height: legacy.ended_height,
+            match_id: None,
+            variant: None,
+            require_ready: false,
+            ready: Vec::new(),
+        };
+        GAMES.insert(storage, &game_id, &game)?;
+        if game.status == GameStatus::Pending {
+            list_open_game(storage, &game)?;
+        }
+        if !game.status.is_finished() {
+            for player in [&game.white, &game.black].into_iter().flatten() {
+                enter_active_game(storage, player, None)?;
+            }
+        }
+        let seated = [&game.white, &game.black].into_iter().flatten().count() as u128;
+        update_counters(storage, |counters| {
+            counters.move_game(None, Some(game.status));
+            if !game.paid {
+                counters.escrowed = counters.escrowed.saturating_add(game.wager * seated);
+            }
+        })?;
+    }
+    // Version 1 already indexed every seated player
+    let players = PLAYER_GAMES.get_len(storage)?;
+    update_counters(storage, |counters| counters.players = u64::from(players))
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    if let Some(game_id) = execute_game_id(&msg) {
+        validate_game_id(deps.storage, game_id)?;
+    }
     match msg {
-        ExecuteMsg::CreateGame {} => create_game(deps, env, info.sender.clone(), info.funds),
-        ExecuteMsg::JoinGame { game_id } => {
-            join_game(deps, env, info.sender.clone(), game_id, info)
+        ExecuteMsg::CreateGame {
+            join_deadline_blocks,
+            referrer,
+            move_timeout_blocks,
+            fen,
+            preferred_color,
+            opponent,
+            variant,
+            require_ready,
+        } => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            let options = GameOptions {
+                join_deadline_blocks,
+                referrer,
+                move_timeout_blocks,
+                fen,
+                preferred_color,
+                opponent,
+                variant,
+                require_ready,
+            };
+            create_game(deps, env, info.sender.clone(), wager, options)
+        }
+        ExecuteMsg::JoinGame { game_id, referrer } => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            join_game(deps, env, info.sender.clone(), game_id, wager, referrer)
+        }
+        ExecuteMsg::QuickMatch {} => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            quick_match(deps, env, info.sender.clone(), wager)
+        }
+        ExecuteMsg::Rematch { game_id } => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            rematch(deps, env, info.sender.clone(), game_id, wager)
+        }
+        ExecuteMsg::CreateMatch { best_of } => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            create_match(deps, info.sender.clone(), best_of, wager)
         }
+        ExecuteMsg::JoinMatch { match_id } => {
+            let wager = native_wager(deps.storage, &info.funds)?;
+            join_match(deps, env, info.sender.clone(), match_id, wager)
+        }
+        ExecuteMsg::CancelMatch { match_id } => cancel_match(deps, info.sender.clone(), match_id),
+        ExecuteMsg::Receive {
+            sender: _,
+            from,
+            amount,
+            msg,
+        } => receive(deps, env, info, from, amount, msg),
         ExecuteMsg::MakeMove {
             game_id,
             move_from,
@@ -39,275 +444,3461 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             move_to,
             promotion,
         ),
+        ExecuteMsg::Ready { game_id } => ready(deps, env, info.sender.clone(), game_id),
         ExecuteMsg::Resign { game_id } => resign(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::ExtendDeadline {
+            game_id,
+            new_deadline,
+        } => extend_deadline(deps, env, info.sender.clone(), game_id, new_deadline),
+        ExecuteMsg::LeaveGame { game_id } => leave_game(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::ClaimFiftyMove { game_id } => {
+            claim_fifty_move(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::ResignMany { game_ids } => {
+            resign_many(deps, env, info.sender.clone(), game_ids)
+        }
+        ExecuteMsg::SetPremove {
+            game_id,
+            move_from,
+            move_to,
+            promotion,
+        } => set_premove(
+            deps,
+            env,
+            info.sender.clone(),
+            game_id,
+            move_from,
+            move_to,
+            promotion,
+        ),
+        ExecuteMsg::SetPlayerName { name } => set_player_name(deps, env, info.sender.clone(), name),
+        ExecuteMsg::CancelGame { game_id } => cancel_game(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::OfferDraw { game_id } => offer_draw(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::AcceptDraw { game_id } => accept_draw(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::ClaimTimeout { game_id } => {
+            claim_timeout(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::ClaimAbandonment { game_id } => {
+            claim_abandonment(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::OfferTakeback { game_id } => {
+            offer_takeback(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::AcceptTakeback { game_id } => {
+            accept_takeback(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::ClaimDraw { game_id } => claim_draw(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::OfferAbort { game_id } => offer_abort(deps, env, info.sender.clone(), game_id),
+        ExecuteMsg::AcceptAbort { game_id } => {
+            accept_abort(deps, env, info.sender.clone(), game_id)
+        }
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, info.sender.clone(), paused),
+        ExecuteMsg::AdminResolve { game_id, outcome } => {
+            admin_resolve(deps, env, info.sender.clone(), game_id, outcome)
+        }
+        ExecuteMsg::WithdrawFees { amount } => withdraw_fees(deps, info.sender.clone(), amount),
+        ExecuteMsg::ExpireGame { game_id } => expire_game(deps, env, game_id),
+        ExecuteMsg::SeedGame {
+            white,
+            black,
+            moves,
+            wager,
+        } => seed_game(deps, env, info.sender.clone(), white, black, moves, wager),
     }
 }
 
-fn create_game(deps: DepsMut, env: Env, sender: Addr, wager: Vec<Coin>) -> StdResult<Response> {
-    // make sure some funds were sent
-    if wager.len() < 1 {
-        return Err(StdError::generic_err("No funds sent"));
+// Reads a native uscrt wager from the sent funds, if any were sent
+fn native_wager(storage: &dyn Storage, funds: &[Coin]) -> StdResult<Option<u128>> {
+    if funds.is_empty() {
+        return Ok(None);
+    }
+    if CONFIG.load(storage)?.wager_token.is_some() {
+        return Err(StdError::generic_err(
+            "Wagers must be sent through the wager token",
+        ));
+    }
+    // Only the first coin would count towards the wager, so any other coin would be lost
+    if funds.len() > 1 {
+        return Err(StdError::generic_err("Send only uscrt"));
     }
-
     // make sure the funds sent were SCRT (`uscrt` stands for micro-SCRT)
-    if wager[0].denom != "uscrt" {
+    if funds[0].denom != "uscrt" {
         return Err(StdError::generic_err("Bid not SCRT"));
     }
+    Ok(Some(funds[0].amount.u128()))
+}
 
-    let mut game_id = NEXT_GAME_ID.load(deps.storage)?;
-    game_id += 1;
-    let mut new_game_state: GameState = GameState {
-        id: game_id,
-        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-        white: None,
-        black: None,
-        turn: 0,
-        status: GameStatus::Pending,
-        wager: wager[0].amount.u128(),
-    };
+// The second stake has to equal the first exactly; the error says which way it is off
+fn match_wager(sent: u128, expected: u128) -> Result<(), ContractError> {
+    if sent < expected {
+        return Err(ContractError::WagerTooLow);
+    }
+    if sent > expected {
+        return Err(ContractError::WagerTooHigh);
+    }
+    Ok(())
+}
 
-    let bytes: Option<Binary> = env.block.random;
-    if let Some(random_bytes) = bytes {
-        // Use the first byte to decide color
-        if !random_bytes.is_empty() && random_bytes.as_slice()[0] % 2 == 0 {
-            new_game_state.white = Some(sender);
-        } else {
-            new_game_state.black = Some(sender);
+// SNIP-20 Send callback: `from` sent `amount` of the wager token along with a game action
+fn receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: Addr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    match config.wager_token {
+        Some(token) if token.address == info.sender => {}
+        _ => return Err(StdError::generic_err("Unrecognized wager token").into()),
+    }
+    let msg = msg.ok_or_else(|| StdError::generic_err("Missing game action"))?;
+
+    match from_binary(&msg)? {
+        ReceiveMsg::CreateGame {
+            join_deadline_blocks,
+            referrer,
+            move_timeout_blocks,
+            fen,
+            preferred_color,
+            opponent,
+            variant,
+            require_ready,
+        } => {
+            let options = GameOptions {
+                join_deadline_blocks,
+                referrer,
+                move_timeout_blocks,
+                fen,
+                preferred_color,
+                opponent,
+                variant,
+                require_ready,
+            };
+            create_game(deps, env, from, Some(amount.u128()), options)
+        }
+        ReceiveMsg::JoinGame { game_id, referrer } => {
+            validate_game_id(deps.storage, game_id)?;
+            join_game(deps, env, from, game_id, Some(amount.u128()), referrer)
+        }
+        ReceiveMsg::QuickMatch {} => quick_match(deps, env, from, Some(amount.u128())),
+        ReceiveMsg::Rematch { game_id } => {
+            validate_game_id(deps.storage, game_id)?;
+            rematch(deps, env, from, game_id, Some(amount.u128()))
+        }
+        ReceiveMsg::CreateMatch { best_of } => {
+            create_match(deps, from, best_of, Some(amount.u128()))
+        }
+        ReceiveMsg::JoinMatch { match_id } => {
+            join_match(deps, env, from, match_id, Some(amount.u128()))
         }
-    } else {
-        // Fallback: default to white if no randomness
-        new_game_state.white = Some(sender);
     }
+}
 
-    GAMES.insert(deps.storage, &game_id, &new_game_state)?;
-    NEXT_GAME_ID.save(deps.storage, &game_id)?;
+// Ids start at 1 and count up, so anything outside 1..=NEXT_GAME_ID can be turned away as
+// malformed before looking for the game
+fn validate_game_id(storage: &dyn Storage, game_id: u64) -> Result<(), ContractError> {
+    let last_id = NEXT_GAME_ID.may_load(storage)?.unwrap_or(0);
+    if game_id == 0 || game_id > last_id {
+        return Err(ContractError::InvalidGameId { game_id });
+    }
+    Ok(())
+}
 
-    Ok(Response::new().add_attribute("game_id", game_id.to_string()))
+// The single game an execute message acts on, if any
+fn execute_game_id(msg: &ExecuteMsg) -> Option<u64> {
+    match msg {
+        ExecuteMsg::JoinGame { game_id, .. }
+        | ExecuteMsg::MakeMove { game_id, .. }
+        | ExecuteMsg::Ready { game_id }
+        | ExecuteMsg::Resign { game_id }
+        | ExecuteMsg::ExtendDeadline { game_id, .. }
+        | ExecuteMsg::LeaveGame { game_id }
+        | ExecuteMsg::ClaimFiftyMove { game_id }
+        | ExecuteMsg::SetPremove { game_id, .. }
+        | ExecuteMsg::CancelGame { game_id }
+        | ExecuteMsg::ExpireGame { game_id }
+        | ExecuteMsg::OfferDraw { game_id }
+        | ExecuteMsg::AcceptDraw { game_id }
+        | ExecuteMsg::ClaimTimeout { game_id }
+        | ExecuteMsg::ClaimAbandonment { game_id }
+        | ExecuteMsg::OfferTakeback { game_id }
+        | ExecuteMsg::AcceptTakeback { game_id }
+        | ExecuteMsg::ClaimDraw { game_id }
+        | ExecuteMsg::OfferAbort { game_id }
+        | ExecuteMsg::AcceptAbort { game_id }
+        | ExecuteMsg::Rematch { game_id }
+        | ExecuteMsg::AdminResolve { game_id, .. } => Some(*game_id),
+        _ => None,
+    }
 }
 
-fn join_game(
+// Same for queries. CanJoin is left out since it answers `not_found` for a missing game.
+fn query_game_id(msg: &QueryMsg) -> Option<u64> {
+    match msg {
+        QueryMsg::GetGame { game_id }
+        | QueryMsg::Spectators { game_id }
+        | QueryMsg::AllLegalMoves { game_id }
+        | QueryMsg::LegalMoves { game_id }
+        | QueryMsg::Fen { game_id }
+        | QueryMsg::Board { game_id }
+        | QueryMsg::Material { game_id }
+        | QueryMsg::Timeline { game_id }
+        | QueryMsg::Turn { game_id }
+        | QueryMsg::TimeLeft { game_id }
+        | QueryMsg::ProjectedSettlement { game_id, .. }
+        | QueryMsg::GameDetail { game_id }
+        | QueryMsg::ExportPgn { game_id } => Some(*game_id),
+        _ => None,
+    }
+}
+
+// Everything CreateGame can be asked for besides the wager, whether paid natively or by token
+struct GameOptions {
+    join_deadline_blocks: Option<u64>,
+    referrer: Option<Addr>,
+    move_timeout_blocks: Option<u64>,
+    fen: Option<String>,
+    preferred_color: Option<String>,
+    opponent: Option<Addr>,
+    variant: Option<String>,
+    require_ready: Option<bool>,
+}
+
+fn create_game(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: Addr,
-    game_id: u64,
-    info: MessageInfo,
-) -> StdResult<Response> {
-    let game_state = GAMES.get(deps.storage, &game_id);
-    match game_state {
-        Some(mut state) => {
-            // User may just be reconnecting to the game - Not an error
-            if state.white == Some(sender.clone()) || state.black == Some(sender.clone()) {
-                return Ok(Response::default());
-            }
-            if state.white.is_some() && state.black.is_some() {
-                // Both players are in the game - this is a spectator
-                return Ok(Response::default());
-            }
+    wager: Option<u128>,
+    options: GameOptions,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    // Sending no funds creates a free game
+    let wager = wager.unwrap_or(0);
+    let config = CONFIG.load(deps.storage)?;
+    check_wager(&config, wager)?;
 
-            // At this point, we know that an opponent is joining, so need to check wager
-            // make sure some funds were sent
-            if info.funds.len() < 1 {
-                return Err(StdError::generic_err("No funds sent"));
-            }
-            // make sure the funds sent were SCRT (`uscrt` stands for micro-SCRT)
-            if info.funds[0].denom != "uscrt" {
-                return Err(StdError::generic_err("Bid not SCRT"));
-            }
-            // make sure the funds sent match the wager
-            if info.funds[0].amount.u128() != state.wager {
-                return Err(StdError::generic_err("Wager not met"));
+    let referrer = validate_referrer(&deps, &sender, options.referrer)?;
+    let invited = match options.opponent {
+        Some(opponent) => {
+            let opponent = deps.api.addr_validate(opponent.as_str())?;
+            if opponent == sender {
+                return Err(StdError::generic_err("Cannot invite yourself").into());
             }
+            Some(opponent)
+        }
+        None => None,
+    };
+    let move_timeout_blocks = options.move_timeout_blocks;
 
-            // Set the other player to colour
-            if state.white.is_some() {
-                state.black = Some(sender);
-            } else {
-                state.white = Some(sender);
-            }
-            state.status = GameStatus::Active;
-            GAMES.insert(deps.storage, &game_id, &state)?;
-            Ok(Response::default())
+    let join_deadline_height = match options.join_deadline_blocks {
+        Some(blocks) if blocks > MAX_JOIN_WINDOW_BLOCKS => {
+            return Err(StdError::generic_err("Deadline exceeds maximum join window").into());
         }
-        None => Err(StdError::GenericErr {
-            msg: format!("No game found with id {game_id}"),
-        }),
+        Some(blocks) => Some(env.block.height + blocks),
+        None => None,
+    };
+    if move_timeout_blocks == Some(0) {
+        return Err(StdError::generic_err("Move timeout must be at least one block").into());
     }
-}
 
-fn make_move(
-    deps: DepsMut,
-    _env: Env,
-    sender: Addr,
-    game_id: u64,
-    move_from: String,
-    move_to: String,
-    promotion: Option<String>,
-) -> StdResult<Response> {
-    let game_state = GAMES.get(deps.storage, &game_id);
-    match game_state {
-        Some(mut state) => {
-            if state.status != GameStatus::Active {
-                return Err(StdError::GenericErr {
-                    msg: format!("Game has ended."),
-                });
+    let game_id = allocate_game_id(deps.storage)?;
+
+    // Puzzles and handicap games start from a custom position
+    let fen = match (options.variant.as_deref(), options.fen) {
+        (None | Some("standard"), Some(fen)) => {
+            validate_start_position(&fen).map_err(StdError::generic_err)?
+        }
+        (None | Some("standard"), None) => STARTING_FEN.to_string(),
+        (Some("chess960"), None) => {
+            let random = env.block.random.as_ref().ok_or_else(|| {
+                StdError::generic_err("No randomness to pick a Chess960 position")
+            })?;
+            // 2^64 isn't a multiple of 960, but the skew that leaves is below 2^-54, where a
+            // 16-bit seed would favour some positions by 1 in 68
+            let mut bytes = [0u8; 8];
+            for (slot, byte) in bytes.iter_mut().zip(random.as_slice()) {
+                *slot = *byte;
             }
-            if Some(sender.clone()) == state.white && state.turn % 2 != 0 {
-                // Not whites turn
-                return Err(StdError::GenericErr {
-                    msg: format!("It is blacks turn"),
-                });
-            } else if Some(sender.clone()) == state.black && state.turn % 2 == 0 {
-                // Not blacks turn
-                return Err(StdError::GenericErr {
-                    msg: format!("It is whites turn"),
-                });
-            } else if Some(sender.clone()) != state.black && Some(sender.clone()) != state.white {
-                // Not one of the players
-                return Err(StdError::GenericErr {
-                    msg: format!("Not a player"),
-                });
+            let number = (u64::from_be_bytes(bytes) % 960) as u16;
+            validate_start_position(&chess960_fen(number)).map_err(StdError::generic_err)?
+        }
+        (Some("chess960"), Some(_)) => {
+            return Err(
+                StdError::generic_err("Chess960 games pick their own starting position").into(),
+            );
+        }
+        (Some(other), _) => {
+            return Err(StdError::generic_err(format!(
+                "Unknown variant {other}: expected standard or chess960"
+            ))
+            .into());
+        }
+    };
+    let mut new_game_state = pending_game(game_id, fen, wager, &env);
+    new_game_state.variant = options.variant.filter(|variant| variant != "standard");
+    new_game_state.join_deadline_height = join_deadline_height;
+    new_game_state.move_timeout_blocks = move_timeout_blocks;
+    new_game_state.invited = invited;
+    new_game_state.require_ready = options.require_ready.unwrap_or(false);
+
+    let creator_is_white = match options.preferred_color.as_deref() {
+        Some("white") => true,
+        Some("black") => false,
+        None | Some("random") => match coin_flip(&env) {
+            Some(heads) => heads,
+            // Without randomness the creator only sits as White until the opponent's join block
+            // decides the colours
+            None => {
+                new_game_state.provisional_colors = true;
+                true
             }
-            let (new_fen, status) =
-                validate_move(&state.fen, &move_from, &move_to, promotion.as_deref()).map_err(
-                    |_| StdError::GenericErr {
-                        msg: "Illegal Move".to_string(),
-                    },
-                )?;
-            state.fen = new_fen;
-            state.status = match status {
-                chess::BoardStatus::Ongoing => GameStatus::Active,
-                chess::BoardStatus::Stalemate => GameStatus::Stalemate,
-                chess::BoardStatus::Checkmate => {
-                    if state.turn % 2 == 0 {
-                        GameStatus::WhiteWins
-                    } else {
-                        GameStatus::BlackWins
-                    }
-                }
-            };
-            state.turn += 1;
-            GAMES.insert(deps.storage, &game_id, &state)?;
-            let wager_messages = handle_wager(state);
-            return Ok(Response::default().add_messages(wager_messages));
+        },
+        Some(other) => {
+            return Err(StdError::generic_err(format!(
+                "Unknown colour {other}: expected white, black or random"
+            ))
+            .into());
         }
+    };
+    if creator_is_white {
+        new_game_state.white = Some(sender);
+        new_game_state.white_referrer = referrer;
+    } else {
+        new_game_state.black = Some(sender);
+        new_game_state.black_referrer = referrer;
+    }
 
-        None => Err(StdError::GenericErr {
-            msg: format!("No game found with id {game_id}"),
-        }),
+    GAMES.insert(deps.storage, &game_id, &new_game_state)?;
+    list_open_game(deps.storage, &new_game_state)?;
+    update_counters(deps.storage, |counters| {
+        counters.move_game(None, Some(GameStatus::Pending));
+        counters.escrowed = counters.escrowed.saturating_add(wager);
+    })?;
+    if let Some(creator) = new_game_state
+        .white
+        .as_ref()
+        .or(new_game_state.black.as_ref())
+    {
+        index_player_game(deps.storage, creator, game_id)?;
+        enter_active_game(deps.storage, creator, config.max_active_games_per_player)?;
+    }
+
+    // Enough for the opponent to build their JoinGame straight from this transaction's logs
+    let denom = match &config.wager_token {
+        Some(token) => token.address.to_string(),
+        None => "uscrt".to_string(),
+    };
+    let mut response = Response::new()
+        .add_attribute("action", "create")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("wager", wager.to_string())
+        .add_attribute("denom", denom);
+    // Most Chess960 setups can't castle at all here, so say which rights the game really has
+    if new_game_state.variant.is_some() {
+        let castling = new_game_state.fen.split_whitespace().nth(2).unwrap_or("-");
+        response = response.add_attribute("castling", castling);
     }
+    Ok(response)
 }
 
-fn resign(deps: DepsMut, _env: Env, sender: Addr, game_id: u64) -> StdResult<Response> {
-    let game_state = GAMES.get(deps.storage, &game_id);
-    match game_state {
-        Some(mut state) => {
-            if state.status == GameStatus::Active {
-                if state.white == Some(sender.clone()) {
-                    state.status = GameStatus::WhiteResigned;
-                } else if state.black == Some(sender.clone()) {
-                    state.status = GameStatus::BlackResigned;
-                }
-                GAMES.insert(deps.storage, &game_id, &state)?;
+// A fair coin from every byte of the block's randomness, or None if the block has none
+fn coin_flip(env: &Env) -> Option<bool> {
+    let random = env.block.random.as_ref()?;
+    if random.is_empty() {
+        return None;
+    }
+    Some(random.as_slice().iter().fold(0u8, |acc, byte| acc ^ byte) % 2 == 0)
+}
 
-                // Get the wager handling messages and add them to the response
-                let wager_messages = handle_wager(state);
-                return Ok(Response::default().add_messages(wager_messages));
-            }
-            return Err(StdError::GenericErr {
-                msg: "Game is not active".to_string(),
-            });
+// Joiners must match the creator's wager exactly, so checking it at creation bounds both stakes.
+// Free games have no stake to bound.
+fn check_wager(config: &Config, wager: u128) -> StdResult<()> {
+    // Reject wagers whose pot could never be paid out, before anyone else stakes on it
+    pot_size(wager)?;
+    if wager > 0 && wager < config.min_wager {
+        return Err(StdError::generic_err(format!(
+            "Wager is below the minimum of {}",
+            config.min_wager
+        )));
+    }
+    if let Some(max_wager) = config.max_wager {
+        if wager > max_wager {
+            return Err(StdError::generic_err(format!(
+                "Wager is above the maximum of {max_wager}"
+            )));
         }
-        None => Err(StdError::GenericErr {
-            msg: format!("No game found with id {game_id}"),
-        }),
     }
+    Ok(())
 }
 
-fn handle_wager(game: GameState) -> Vec<CosmosMsg> {
-    let mut messages = Vec::new();
+fn allocate_game_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    // Missing counter means instantiate (or a migration) never stored it
+    let game_id = NEXT_GAME_ID
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("Contract not initialized"))?
+        + 1;
+    NEXT_GAME_ID.save(storage, &game_id)?;
+    Ok(game_id)
+}
+
+// A game waiting for its players, with no deadline or move timeout
+fn pending_game(game_id: u64, fen: String, wager: u128, env: &Env) -> GameState {
+    GameState {
+        id: game_id,
+        fen: fen.clone(),
+        white: None,
+        black: None,
+        turn: 0,
+        status: GameStatus::Pending,
+        wager,
+        join_deadline_height: None,
+        spectators: Vec::new(),
+        white_referrer: None,
+        black_referrer: None,
+        premove: None,
+        moves: Vec::new(),
+        move_heights: Vec::new(),
+        positions: vec![position_key(&fen)],
+        start_fen: fen,
+        draw_offered_by: None,
+        draw_offered_at: None,
+        takeback_offered_by: None,
+        abort_offered_by: None,
+        last_move_height: env.block.height,
+        last_activity_height: env.block.height,
+        move_timeout_blocks: None,
+        paid: false,
+        invited: None,
+        provisional_colors: false,
+        winner: None,
+        created_height: env.block.height,
+        started_height: None,
+        started_time: None,
+        ended_height: None,
+        match_id: None,
+        variant: None,
+        require_ready: false,
+        ready: Vec::new(),
+    }
+}
+
+// Stakes the wager for a whole best-of-N match, played as a series of free games
+fn create_match(
+    deps: DepsMut,
+    sender: Addr,
+    best_of: u8,
+    wager: Option<u128>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    if best_of % 2 != 1 || best_of > MAX_BEST_OF {
+        return Err(StdError::generic_err(format!(
+            "A match must be best of an odd number of games up to {MAX_BEST_OF}"
+        ))
+        .into());
+    }
+    let wager = wager.unwrap_or(0);
+    check_wager(&CONFIG.load(deps.storage)?, wager)?;
 
-    let amount: u128 = match game.status {
-        GameStatus::Stalemate => game.wager,
-        GameStatus::WhiteWins => game.wager * 2,
-        GameStatus::BlackWins => game.wager * 2,
-        GameStatus::WhiteResigned => game.wager * 2,
-        GameStatus::BlackResigned => game.wager * 2,
-        _ => 0, // Do Nothing
+    // Matches came after the game counter, so the match counter starts empty
+    let match_id = NEXT_MATCH_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+    NEXT_MATCH_ID.save(deps.storage, &match_id)?;
+    let state = MatchState {
+        id: match_id,
+        best_of,
+        wager,
+        creator: sender,
+        opponent: None,
+        creator_wins: 0,
+        opponent_wins: 0,
+        games: Vec::new(),
+        winner: None,
+        paid: false,
     };
+    MATCHES.insert(deps.storage, &match_id, &state)?;
+    update_counters(deps.storage, |counters| {
+        counters.escrowed = counters.escrowed.saturating_add(wager)
+    })?;
 
-    if game.status == GameStatus::Stalemate {
-        // In stalemate, each player gets their wager back
-        let coins_per_player: Vec<Coin> = vec![Coin {
-            denom: "uscrt".to_string(),
-            amount: Uint128::from(game.wager),
-        }];
+    Ok(Response::new()
+        .add_attribute("action", "create_match")
+        .add_attribute("match_id", match_id.to_string()))
+}
 
-        if let (Some(white_addr), Some(black_addr)) = (game.white.clone(), game.black.clone()) {
-            if game.wager > 0 {
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: white_addr.to_string(),
-                    amount: coins_per_player.clone(),
-                }));
+fn join_match(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    match_id: u64,
+    wager: Option<u128>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let mut state = MATCHES
+        .get(deps.storage, &match_id)
+        .ok_or(ContractError::MatchNotFound { match_id })?;
+    if state.opponent.is_some() {
+        return Err(StdError::generic_err("Match already has two players").into());
+    }
+    if sender == state.creator {
+        return Err(StdError::generic_err("Cannot join your own match").into());
+    }
+    match_wager(wager.unwrap_or(0), state.wager)?;
 
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: black_addr.to_string(),
-                    amount: coins_per_player,
-                }));
-            }
-        }
-    } else if amount > 0 {
-        // Winner takes all (both wagers)
-        let coins_to_send: Vec<Coin> = vec![Coin {
-            denom: "uscrt".to_string(),
-            amount: Uint128::from(amount),
-        }];
+    state.opponent = Some(sender);
+    update_counters(deps.storage, |counters| {
+        counters.escrowed = counters.escrowed.saturating_add(state.wager)
+    })?;
+    let config = CONFIG.load(deps.storage)?;
+    let game_id = start_round(
+        deps.storage,
+        &env,
+        &mut state,
+        config.max_active_games_per_player,
+    )?;
+    MATCHES.insert(deps.storage, &match_id, &state)?;
 
-        let winner_addr = match game.status {
-            GameStatus::WhiteWins | GameStatus::BlackResigned => game.white.clone(),
-            GameStatus::BlackWins | GameStatus::WhiteResigned => game.black.clone(),
-            _ => None,
-        };
+    Ok(Response::new()
+        .add_attribute("action", "join_match")
+        .add_attribute("match_id", match_id.to_string())
+        .add_attribute("game_id", game_id.to_string()))
+}
 
-        if let Some(addr) = winner_addr {
-            messages.push(CosmosMsg::Bank(BankMsg::Send {
-                to_address: addr.to_string(),
-                amount: coins_to_send,
-            }));
-        }
+// Until someone joins, the creator can take their stake back; the match is removed
+fn cancel_match(deps: DepsMut, sender: Addr, match_id: u64) -> Result<Response, ContractError> {
+    let state = MATCHES
+        .get(deps.storage, &match_id)
+        .ok_or(ContractError::MatchNotFound { match_id })?;
+    if sender != state.creator {
+        return Err(StdError::generic_err("Only the creator can cancel this match").into());
+    }
+    if state.opponent.is_some() {
+        return Err(StdError::generic_err("Match already has two players").into());
     }
 
-    messages
+    MATCHES.remove(deps.storage, &match_id)?;
+    update_counters(deps.storage, |counters| {
+        counters.escrowed = counters.escrowed.saturating_sub(state.wager)
+    })?;
+    let mut response = Response::new()
+        .add_attribute("action", "cancel_match")
+        .add_attribute("match_id", match_id.to_string());
+    if state.wager > 0 {
+        let config = CONFIG.load(deps.storage)?;
+        response = response.add_message(payout_message(
+            &config,
+            Payout {
+                recipient: state.creator,
+                amount: Uint128::from(state.wager),
+            },
+        )?);
+    }
+    Ok(response)
 }
-#[entry_point]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetGame { game_id } => {
-            return get_game_state(deps, env, game_id);
-        }
-        QueryMsg::ListGames {} => {
-            return all_games(deps, env);
-        }
+
+// Starts the match's next game, with the creator taking White in the first round and colours
+// alternating after that
+fn start_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    state: &mut MatchState,
+    cap: Option<u32>,
+) -> StdResult<u64> {
+    let opponent = state
+        .opponent
+        .clone()
+        .ok_or_else(|| StdError::generic_err("Match has not started"))?;
+    let (white, black) = if state.games.len() % 2 == 1 {
+        (opponent, state.creator.clone())
+    } else {
+        (state.creator.clone(), opponent)
+    };
+
+    let game_id = allocate_game_id(storage)?;
+    let mut game = pending_game(game_id, STARTING_FEN.to_string(), 0, env);
+    game.match_id = Some(state.id);
+    game.status = GameStatus::Active;
+    game.started_height = Some(env.block.height);
+    game.started_time = Some(env.block.time.seconds());
+    for player in [&white, &black] {
+        index_player_game(storage, player, game_id)?;
+        enter_active_game(storage, player, cap)?;
     }
+    game.white = Some(white);
+    game.black = Some(black);
+    GAMES.insert(storage, &game_id, &game)?;
+    update_counters(storage, |counters| {
+        counters.move_game(None, Some(GameStatus::Active))
+    })?;
+    state.games.push(game_id);
+    Ok(game_id)
 }
 
-fn get_game_state(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
-    let game_state = GAMES.get(deps.storage, &game_id);
-    match game_state {
-        Some(state) => Ok(to_binary(&QueryAnswer::GameState(state))?),
-        None => Err(StdError::GenericErr {
-            msg: format!("No game found with id {game_id}"),
-        }),
+// Scores a settled round, then either starts the next one or pays out the match. Drawn rounds
+// don't count, so they are replayed.
+fn finish_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    match_id: u64,
+    game: &GameState,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut state = MATCHES
+        .get(storage, &match_id)
+        .ok_or(ContractError::MatchNotFound { match_id })?;
+    match &game.winner {
+        Some(winner) if *winner == state.creator => state.creator_wins += 1,
+        Some(_) => state.opponent_wins += 1,
+        None => {}
+    }
+
+    let majority = state.best_of / 2 + 1;
+    if state.creator_wins < majority && state.opponent_wins < majority {
+        // Both players already agreed to the whole match, so later rounds ignore the cap
+        start_round(storage, env, &mut state, None)?;
+        MATCHES.insert(storage, &match_id, &state)?;
+        return Ok(Vec::new());
     }
+    state.winner = if state.creator_wins >= majority {
+        Some(state.creator.clone())
+    } else {
+        state.opponent.clone()
+    };
+    handle_match_wager(storage, state)
 }
 
-fn all_games(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let games: Vec<GameState> = GAMES
-        .iter(deps.storage)?
-        .map(|game| {
-            let (_game_id, game_state) = game?;
-            Ok(game_state)
-        })
-        .collect::<StdResult<Vec<GameState>>>()?;
+// The match-level counterpart of handle_wager: the winner takes both stakes, less fees
+fn handle_match_wager(
+    storage: &mut dyn Storage,
+    mut state: MatchState,
+) -> StdResult<Vec<CosmosMsg>> {
+    if state.paid {
+        return Ok(Vec::new());
+    }
+    state.paid = true;
+    MATCHES.insert(storage, &state.id, &state)?;
+
+    let config = CONFIG.load(storage)?;
+    let pot = pot_size(state.wager)?;
+    add_volume(storage, pot)?;
+    update_counters(storage, |counters| {
+        counters.escrowed = counters.escrowed.saturating_sub(pot.u128())
+    })?;
+    let winner = match state.winner {
+        Some(winner) => winner,
+        None => return Ok(Vec::new()),
+    };
+    let payouts = decisive_payouts(&config, pot, winner, [&None, &None])?;
+    payout_messages(storage, &config, payouts)
+}
+
+// Pairs the same two players again with colours swapped; the opponent still joins and pays
+fn rematch(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    wager: Option<u128>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(previous) => {
+            if !previous.status.is_finished() {
+                return Err(StdError::generic_err("Only a finished game can be rematched").into());
+            }
+            let (white, black) = match (previous.white.clone(), previous.black.clone()) {
+                (Some(white), Some(black)) => (white, black),
+                _ => return Err(ContractError::NotAPlayer),
+            };
+            // Only the two players may ask, whatever they sent
+            let sender_was_white = if sender == white {
+                true
+            } else if sender == black {
+                false
+            } else {
+                return Err(ContractError::NotAPlayer);
+            };
+            let wager = wager.unwrap_or(0);
+            match_wager(wager, previous.wager)?;
+            // The limits may have changed since the original game was created
+            let config = CONFIG.load(deps.storage)?;
+            check_wager(&config, wager)?;
 
-    Ok(to_binary(&QueryAnswer::AllGames(games))?)
+            let new_game_id = allocate_game_id(deps.storage)?;
+            let mut state = pending_game(new_game_id, previous.start_fen.clone(), wager, &env);
+            state.move_timeout_blocks = previous.move_timeout_blocks;
+            state.variant = previous.variant.clone();
+            state.require_ready = previous.require_ready;
+            if sender_was_white {
+                state.black = Some(sender.clone());
+                state.black_referrer = previous.white_referrer;
+                state.invited = Some(black);
+            } else {
+                state.white = Some(sender.clone());
+                state.white_referrer = previous.black_referrer;
+                state.invited = Some(white);
+            }
+            GAMES.insert(deps.storage, &new_game_id, &state)?;
+            update_counters(deps.storage, |counters| {
+                counters.move_game(None, Some(GameStatus::Pending));
+                counters.escrowed = counters.escrowed.saturating_add(wager);
+            })?;
+            index_player_game(deps.storage, &sender, new_game_id)?;
+            enter_active_game(deps.storage, &sender, config.max_active_games_per_player)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "rematch")
+                .add_attribute("previous_game_id", game_id.to_string())
+                .add_attribute("game_id", new_game_id.to_string()))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Whether `player` could join the game right now, and as what. join_game acts on this same
+// answer, so the CanJoin query can't drift from what joining actually does.
+fn join_eligibility(
+    state: &GameState,
+    player: &Addr,
+    height: u64,
+    max_spectators: u32,
+) -> JoinEligibility {
+    // Nothing to join or watch once the result is in
+    if state.status.is_finished() {
+        return JoinEligibility::Finished;
+    }
+    if state.white.as_ref() == Some(player) || state.black.as_ref() == Some(player) {
+        return JoinEligibility::AlreadyPlayer;
+    }
+    if state.white.is_some() && state.black.is_some() {
+        // Spectators already registered are let back in whatever the limit
+        if !state.spectators.contains(player) && state.spectators.len() >= max_spectators as usize {
+            return JoinEligibility::Full;
+        }
+        return JoinEligibility::Spectate;
+    }
+    if let Some(deadline) = state.join_deadline_height {
+        if height > deadline {
+            return JoinEligibility::Expired;
+        }
+    }
+    if state.invited.is_some() && state.invited.as_ref() != Some(player) {
+        return JoinEligibility::Private;
+    }
+    JoinEligibility::Joinable {
+        wager: Uint128::from(state.wager),
+    }
+}
+
+fn join_game(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    wager: Option<u128>,
+    referrer: Option<Addr>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            let config = CONFIG.load(deps.storage)?;
+            match join_eligibility(&state, &sender, env.block.height, config.max_spectators) {
+                JoinEligibility::Finished => return Err(ContractError::GameFinished),
+                JoinEligibility::AlreadyPlayer => {
+                    // Only one seat is taken, so the creator calling again would play both colours
+                    if state.white.is_none() || state.black.is_none() {
+                        return Err(StdError::generic_err("Cannot play yourself").into());
+                    }
+                    reject_join_funds(wager)?;
+                    // User may just be reconnecting to the game - Not an error
+                    let color = if state.white == Some(sender) {
+                        "white"
+                    } else {
+                        "black"
+                    };
+                    return Ok(Response::new()
+                        .add_attribute("action", "reconnect")
+                        .add_attribute("game_id", game_id.to_string())
+                        .add_attribute("role", color)
+                        .add_attribute("color", color));
+                }
+                JoinEligibility::Full => {
+                    return Err(StdError::generic_err("Spectator limit reached").into())
+                }
+                JoinEligibility::Spectate => {
+                    // Both players are in the game - this is a spectator
+                    reject_join_funds(wager)?;
+                    if !state.spectators.contains(&sender) {
+                        state.spectators.push(sender.clone());
+                        GAMES.insert(deps.storage, &game_id, &state)?;
+
+                        let mut watching =
+                            SPECTATING.get(deps.storage, &sender).unwrap_or_default();
+                        watching.push(game_id);
+                        SPECTATING.insert(deps.storage, &sender, &watching)?;
+                    }
+                    return Ok(Response::new()
+                        .add_attribute("action", "spectate")
+                        .add_attribute("game_id", game_id.to_string())
+                        .add_attribute("role", "spectator"));
+                }
+                JoinEligibility::Expired => {
+                    return Err(StdError::generic_err("Game has expired").into())
+                }
+                JoinEligibility::Private => {
+                    return Err(StdError::generic_err("Game is private").into())
+                }
+                JoinEligibility::NotFound => return Err(ContractError::GameNotFound { game_id }),
+                JoinEligibility::Joinable { .. } => {}
+            }
+
+            // At this point, we know that an opponent is joining, so need to check wager
+            // Sending no funds only matches a free game
+            let wager = wager.unwrap_or(0);
+            match_wager(wager, state.wager)?;
+
+            let referrer = validate_referrer(&deps, &sender, referrer)?;
+
+            index_player_game(deps.storage, &sender, game_id)?;
+            enter_active_game(deps.storage, &sender, config.max_active_games_per_player)?;
+
+            // Set the other player to colour
+            let mut color = if state.white.is_some() {
+                state.black = Some(sender);
+                state.black_referrer = referrer;
+                "black"
+            } else {
+                state.white = Some(sender);
+                state.white_referrer = referrer;
+                "white"
+            };
+            if state.provisional_colors {
+                state.provisional_colors = false;
+                // If this block has no randomness either, the creator keeps White
+                if !coin_flip(&env).unwrap_or(true) {
+                    std::mem::swap(&mut state.white, &mut state.black);
+                    std::mem::swap(&mut state.white_referrer, &mut state.black_referrer);
+                    color = "white";
+                }
+            }
+            // The joiner took the empty seat and a swap keeps both filled
+            debug_assert!(state.white.is_some() && state.black.is_some());
+            // A game that asks for it waits for both players to send Ready before it begins
+            state.status = if state.require_ready {
+                GameStatus::Ready
+            } else {
+                GameStatus::Active
+            };
+            let status = state.status;
+            update_counters(deps.storage, |counters| {
+                counters.move_game(Some(GameStatus::Pending), Some(status));
+                counters.escrowed = counters.escrowed.saturating_add(wager);
+            })?;
+            state.last_activity_height = env.block.height;
+            if state.status == GameStatus::Active {
+                start_clock(&mut state, &env);
+            }
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            unlist_open_game(deps.storage, &state)?;
+            Ok(Response::new()
+                .add_attribute("action", "join")
+                .add_attribute("game_id", game_id.to_string())
+                // The joiner's final colour, after any provisional swap
+                .add_attribute("assigned_color", color)
+                .add_attribute("role", color)
+                .add_attribute("color", color))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Only taking a seat stakes a wager; anything sent with a reconnect or to spectate would be
+// stuck in the contract, outside escrow
+fn reject_join_funds(wager: Option<u128>) -> StdResult<()> {
+    if wager.unwrap_or(0) != 0 {
+        return Err(StdError::generic_err(
+            "Spectators and reconnecting players must not send funds",
+        ));
+    }
+    Ok(())
+}
+
+// White's clock starts once the game begins
+fn start_clock(state: &mut GameState, env: &Env) {
+    state.last_move_height = env.block.height;
+    state.started_height = Some(env.block.height);
+    state.started_time = Some(env.block.time.seconds());
+}
+
+fn ready(deps: DepsMut, env: Env, sender: Addr, game_id: u64) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Ready {
+                return Err(StdError::generic_err("Game is not waiting for players").into());
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            if state.ready.contains(&sender) {
+                return Err(StdError::generic_err("Already ready").into());
+            }
+            state.ready.push(sender);
+            state.last_activity_height = env.block.height;
+
+            let started = state.ready.len() == 2;
+            if started {
+                state.status = GameStatus::Active;
+                update_counters(deps.storage, |counters| {
+                    counters.move_game(Some(GameStatus::Ready), Some(GameStatus::Active));
+                })?;
+                start_clock(&mut state, &env);
+            }
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::new()
+                .add_attribute("action", "ready")
+                .add_attribute("game_id", game_id.to_string())
+                .add_attribute("started", started.to_string()))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Nobody can have moved yet in a game still waiting on Ready, so leaving calls it off as an
+// abort, refunding both wagers
+fn abort_unready(
+    storage: &mut dyn Storage,
+    env: &Env,
+    mut state: GameState,
+) -> StdResult<Vec<CosmosMsg>> {
+    state.status = GameStatus::Aborted;
+    GAMES.insert(storage, &state.id, &state)?;
+    handle_wager(storage, env, state, GameStatus::Ready)
+}
+
+// Takes the seat in the oldest standard game waiting at exactly this wager, falling back to
+// opening a new one with default options when there is none
+fn quick_match(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    wager: Option<u128>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let amount = wager.unwrap_or(0);
+    let max_spectators = CONFIG.load(deps.storage)?.max_spectators;
+    let open_ids = OPEN_GAMES.get(deps.storage, &amount).unwrap_or_default();
+
+    // The lobby index keeps each wager's games in id order, so the first compatible one is the
+    // oldest
+    let open_game = open_ids
+        .into_iter()
+        .find(|game_id| match GAMES.get(deps.storage, game_id) {
+            Some(state) => {
+                state.status == GameStatus::Pending
+                    && state.variant.is_none()
+                    && state.start_fen == STARTING_FEN
+                    && matches!(
+                        join_eligibility(&state, &sender, env.block.height, max_spectators),
+                        JoinEligibility::Joinable { .. }
+                    )
+            }
+            None => false,
+        });
+
+    match open_game {
+        Some(game_id) => {
+            Ok(join_game(deps, env, sender, game_id, wager, None)?
+                .add_attribute("matched", "joined"))
+        }
+        None => {
+            let options = GameOptions {
+                join_deadline_blocks: None,
+                referrer: None,
+                move_timeout_blocks: None,
+                fen: None,
+                preferred_color: None,
+                opponent: None,
+                variant: None,
+                require_ready: None,
+            };
+            Ok(create_game(deps, env, sender, wager, options)?.add_attribute("matched", "created"))
+        }
+    }
+}
+
+fn update_counters<F: FnOnce(&mut Counters)>(
+    storage: &mut dyn Storage,
+    action: F,
+) -> StdResult<()> {
+    let mut counters = COUNTERS.may_load(storage)?.unwrap_or_default();
+    action(&mut counters);
+    COUNTERS.save(storage, &counters)
+}
+
+// Counts a game the player now has a seat in, refusing it if they are already at `cap`
+fn enter_active_game(storage: &mut dyn Storage, player: &Addr, cap: Option<u32>) -> StdResult<()> {
+    let active = ACTIVE_GAMES.get(storage, player).unwrap_or(0);
+    if let Some(cap) = cap {
+        if active >= cap {
+            return Err(StdError::generic_err(format!(
+                "Player already has the maximum of {cap} active games"
+            )));
+        }
+    }
+    ACTIVE_GAMES.insert(storage, player, &(active + 1))
+}
+
+// Stops counting a game that has finished or been cancelled
+fn leave_active_game(storage: &mut dyn Storage, player: &Addr) -> StdResult<()> {
+    match ACTIVE_GAMES.get(storage, player).unwrap_or(0) {
+        0 | 1 => ACTIVE_GAMES.remove(storage, player),
+        active => ACTIVE_GAMES.insert(storage, player, &(active - 1)),
+    }
+}
+
+fn index_player_game(storage: &mut dyn Storage, player: &Addr, game_id: u64) -> StdResult<()> {
+    let mut game_ids = match PLAYER_GAMES.get(storage, player) {
+        Some(game_ids) => game_ids,
+        None => {
+            update_counters(storage, |counters| counters.players += 1)?;
+            Vec::new()
+        }
+    };
+    // Joining an older game after creating a newer one must not break the ordering
+    if let Err(position) = game_ids.binary_search(&game_id) {
+        game_ids.insert(position, game_id);
+        PLAYER_GAMES.insert(storage, player, &game_ids)?;
+    }
+    Ok(())
+}
+
+// Adds a new pending game to the lobby index, unless it is kept for an invited opponent
+fn list_open_game(storage: &mut dyn Storage, state: &GameState) -> StdResult<()> {
+    if state.invited.is_some() {
+        return Ok(());
+    }
+    let mut game_ids = OPEN_GAMES.get(storage, &state.wager).unwrap_or_default();
+    if let Err(position) = game_ids.binary_search(&state.id) {
+        game_ids.insert(position, state.id);
+        OPEN_GAMES.insert(storage, &state.wager, &game_ids)?;
+    }
+    Ok(())
+}
+
+// Takes a game out of the lobby index once it has been joined, cancelled or expired
+fn unlist_open_game(storage: &mut dyn Storage, state: &GameState) -> StdResult<()> {
+    let mut game_ids = OPEN_GAMES.get(storage, &state.wager).unwrap_or_default();
+    if let Ok(position) = game_ids.binary_search(&state.id) {
+        game_ids.remove(position);
+        if game_ids.is_empty() {
+            OPEN_GAMES.remove(storage, &state.wager)?;
+        } else {
+            OPEN_GAMES.insert(storage, &state.wager, &game_ids)?;
+        }
+    }
+    Ok(())
+}
+
+// Forgets a game that no longer exists, keeping the player's (possibly now empty) entry so they
+// aren't counted as a new player next time
+fn unindex_player_game(storage: &mut dyn Storage, player: &Addr, game_id: u64) -> StdResult<()> {
+    if let Some(mut game_ids) = PLAYER_GAMES.get(storage, player) {
+        if let Ok(position) = game_ids.binary_search(&game_id) {
+            game_ids.remove(position);
+            PLAYER_GAMES.insert(storage, player, &game_ids)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_referrer(
+    deps: &DepsMut,
+    sender: &Addr,
+    referrer: Option<Addr>,
+) -> StdResult<Option<Addr>> {
+    match referrer {
+        Some(addr) => {
+            let addr = deps.api.addr_validate(addr.as_str())?;
+            if &addr == sender {
+                return Err(StdError::generic_err("Cannot refer yourself"));
+            }
+            Ok(Some(addr))
+        }
+        None => Ok(None),
+    }
+}
+
+fn leave_game(
+    deps: DepsMut,
+    _env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if !state.spectators.contains(&sender) {
+                return Err(StdError::generic_err("Not a spectator").into());
+            }
+            state.spectators.retain(|spectator| spectator != &sender);
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            stop_spectating(deps.storage, &sender, game_id)?;
+            Ok(Response::default())
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Takes a game off the spectator's watch list
+fn stop_spectating(storage: &mut dyn Storage, spectator: &Addr, game_id: u64) -> StdResult<()> {
+    let mut watching = SPECTATING.get(storage, spectator).unwrap_or_default();
+    watching.retain(|id| *id != game_id);
+    if watching.is_empty() {
+        SPECTATING.remove(storage, spectator)
+    } else {
+        SPECTATING.insert(storage, spectator, &watching)
+    }
+}
+
+fn make_move(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    move_from: String,
+    move_to: String,
+    promotion: Option<Promotion>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            // A finished game must never reach handle_wager again, or the pot would be paid twice
+            if state.status == GameStatus::Pending {
+                return Err(StdError::generic_err("Game has not started").into());
+            }
+            if state.status == GameStatus::Ready {
+                return Err(StdError::generic_err("Both players must be ready first").into());
+            }
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameFinished);
+            }
+            // Anyone without a seat is told so before whose turn it is comes into it
+            if Some(sender.clone()) != state.white && Some(sender.clone()) != state.black {
+                return Err(ContractError::NotAPlayer);
+            }
+            // The FEN knows whose move it is, even in games that start with Black to move
+            let white_turn = white_to_move(&state.fen);
+            let side_to_move = if white_turn {
+                &state.white
+            } else {
+                &state.black
+            };
+            if side_to_move.as_ref() != Some(&sender) {
+                return Err(ContractError::NotYourTurn {
+                    side_to_move: if white_turn { "white" } else { "black" },
+                });
+            }
+            // Pass the reason through so clients can tell malformed squares from illegal moves
+            let height = env.block.height;
+            let played_move = apply_move(&mut state, &move_from, &move_to, promotion, height)
+                .map_err(ContractError::IllegalMove)?;
+            let config = CONFIG.load(deps.storage)?;
+            let max_plies = config.max_plies;
+            cap_plies(&mut state, max_plies);
+            let played = state.moves.last().cloned().unwrap_or_default();
+
+            // The opponent may have queued a reply; it's discarded if the move made it illegal or
+            // ended the game
+            let mut response = Response::new()
+                .add_attribute("action", "move")
+                .add_attribute("game_id", game_id.to_string())
+                .add_attribute("move", played)
+                .add_attribute("san", played_move.san);
+            if let Some(piece) = played_move.captured {
+                response = response.add_attribute("captured", promotion_letter(piece));
+            }
+            if let Some(premove) = state.premove.take() {
+                let applied = if state.status == GameStatus::Active {
+                    apply_move(
+                        &mut state,
+                        &premove.move_from,
+                        &premove.move_to,
+                        premove.promotion,
+                        height,
+                    )
+                    .ok()
+                } else {
+                    None
+                };
+                match applied {
+                    Some(premove_move) => {
+                        cap_plies(&mut state, max_plies);
+                        let premove_played = state.moves.last().cloned().unwrap_or_default();
+                        response = response
+                            .add_attribute("premove", "applied")
+                            .add_attribute("premove_move", premove_played)
+                            .add_attribute("premove_san", premove_move.san);
+                        if let Some(piece) = premove_move.captured {
+                            response =
+                                response.add_attribute("premove_captured", promotion_letter(piece));
+                        }
+                    }
+                    None => response = response.add_attribute("premove", "discarded"),
+                }
+            }
+
+            expire_draw_offer(&mut state, config.draw_offer_plies);
+
+            state.last_move_height = env.block.height;
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            // Reflects the position after any premove that was played
+            response = response
+                .add_attribute("new_fen", state.fen.clone())
+                .add_attribute("status", (state.status as i32).to_string());
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            return Ok(response.add_messages(wager_messages));
+        }
+
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Splits a move as `GameState.moves` records it, e.g. "e7e8q", into squares and promotion
+fn split_coordinate_move(text: &str) -> Result<(&str, &str, Option<Promotion>), &'static str> {
+    if !text.is_ascii() || !(4..=5).contains(&text.len()) {
+        return Err("Invalid move notation");
+    }
+    let (move_from, rest) = text.split_at(2);
+    let (move_to, promotion) = rest.split_at(2);
+    if promotion.is_empty() {
+        return Ok((move_from, move_to, None));
+    }
+    match Promotion::from_letter(promotion) {
+        Some(promotion) => Ok((move_from, move_to, Some(promotion))),
+        None => Err("Invalid promotion piece"),
+    }
+}
+
+// Stalling can't grow a game's history without bound: at the cap it ends drawn and refunded
+fn cap_plies(state: &mut GameState, max_plies: Option<u32>) {
+    if let Some(max_plies) = max_plies {
+        if state.status == GameStatus::Active && state.moves.len() >= max_plies as usize {
+            state.status = GameStatus::DrawMaxMoves;
+        }
+    }
+}
+
+// Plays a move for the side to move, updating the FEN, status and turn counter
+fn apply_move(
+    state: &mut GameState,
+    move_from: &str,
+    move_to: &str,
+    promotion: Option<Promotion>,
+    height: u64,
+) -> Result<ValidatedMove, &'static str> {
+    // Only a move that validates is ever recorded in the history
+    let validated = validate_move(&state.fen, move_from, move_to, promotion)?;
+    state.fen = validated.fen.clone();
+    state.moves.push(format!(
+        "{}{}{}",
+        move_from,
+        move_to,
+        promotion.map_or("", |promotion| promotion.letter())
+    ));
+    state.move_heights.push(height);
+    // Playing on declines any takeback or abort offer; draw offers expire in make_move
+    state.takeback_offered_by = None;
+    state.abort_offered_by = None;
+
+    // A capture or pawn move means no earlier position can ever come back
+    let position = position_key(&state.fen);
+    let halfmove = u32::from(validated.halfmove_clock);
+    if halfmove == 0 {
+        state.positions.clear();
+    }
+    let occurrences = state.positions.iter().filter(|p| **p == position).count() + 1;
+    state.positions.push(position);
+
+    state.status = match validated.outcome {
+        // Threefold repetition and fifty moves only allow a claim, but fivefold repetition and
+        // seventy-five moves each without a capture or pawn move end the game on the spot
+        // (checkmate on that move still counts)
+        MoveOutcome::Ongoing if occurrences >= 5 => GameStatus::DrawRepetition,
+        MoveOutcome::Ongoing if halfmove >= SEVENTY_FIVE_MOVE_PLIES => GameStatus::DrawFiftyMove,
+        MoveOutcome::Ongoing => GameStatus::Active,
+        MoveOutcome::Stalemate => GameStatus::Stalemate,
+        MoveOutcome::InsufficientMaterial => GameStatus::DrawInsufficientMaterial,
+        MoveOutcome::Checkmate => {
+            // The side left to move is the one that got mated
+            if white_to_move(&state.fen) {
+                GameStatus::BlackWins
+            } else {
+                GameStatus::WhiteWins
+            }
+        }
+    };
+    state.turn += 1;
+    Ok(validated)
+}
+
+fn set_premove(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    move_from: String,
+    move_to: String,
+    promotion: Option<Promotion>,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            let waiting_player = if white_to_move(&state.fen) {
+                state.black.clone()
+            } else {
+                state.white.clone()
+            };
+            if Some(sender.clone()) != waiting_player {
+                if state.white == Some(sender.clone()) || state.black == Some(sender.clone()) {
+                    return Err(StdError::generic_err("It is your turn").into());
+                }
+                return Err(ContractError::NotAPlayer);
+            }
+
+            state.premove = Some(Premove {
+                player: sender,
+                move_from,
+                move_to,
+                promotion,
+            });
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::default())
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn resign(deps: DepsMut, env: Env, sender: Addr, game_id: u64) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            // With no opponent there is nobody to lose to, so resigning cancels and refunds instead
+            if state.status == GameStatus::Pending {
+                if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                    return Err(ContractError::NotAPlayer);
+                }
+                let refund_messages = cancel_pending(deps.storage, game_id, &state)?;
+                return Ok(Response::new()
+                    .add_attribute("action", "resign")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("cancelled", game_id.to_string())
+                    .add_messages(refund_messages));
+            }
+            if state.status == GameStatus::Ready {
+                if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                    return Err(ContractError::NotAPlayer);
+                }
+                let refund_messages = abort_unready(deps.storage, &env, state)?;
+                return Ok(Response::new()
+                    .add_attribute("action", "resign")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("status", (GameStatus::Aborted as i32).to_string())
+                    .add_messages(refund_messages));
+            }
+            if state.status == GameStatus::Active {
+                if state.white == Some(sender.clone()) {
+                    state.status = GameStatus::WhiteResigned;
+                } else if state.black == Some(sender.clone()) {
+                    state.status = GameStatus::BlackResigned;
+                } else {
+                    return Err(ContractError::NotAPlayer);
+                }
+                GAMES.insert(deps.storage, &game_id, &state)?;
+                let status = (state.status as i32).to_string();
+
+                // Get the wager handling messages and add them to the response
+                let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+                return Ok(Response::new()
+                    .add_attribute("action", "resign")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("status", status)
+                    .add_messages(wager_messages));
+            }
+            Err(ContractError::GameNotActive)
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn resign_many(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    if game_ids.len() > MAX_BATCH_SIZE {
+        return Err(StdError::generic_err(format!(
+            "Cannot exit more than {MAX_BATCH_SIZE} games at once"
+        ))
+        .into());
+    }
+    let mut messages: Vec<CosmosMsg> = Vec::new();
+    let mut resigned: Vec<String> = Vec::new();
+    let mut cancelled: Vec<String> = Vec::new();
+    // Each settled game's resulting status, as Resign reports it
+    let mut statuses: Vec<(String, String)> = Vec::new();
+    for game_id in game_ids {
+        let mut state = match GAMES.get(deps.storage, &game_id) {
+            Some(state) => state,
+            None => continue,
+        };
+        let is_white = state.white == Some(sender.clone());
+        let is_black = state.black == Some(sender.clone());
+        if !is_white && !is_black {
+            // Not one of the sender's games
+            continue;
+        }
+
+        match state.status {
+            GameStatus::Active => {
+                state.status = if is_white {
+                    GameStatus::WhiteResigned
+                } else {
+                    GameStatus::BlackResigned
+                };
+                GAMES.insert(deps.storage, &game_id, &state)?;
+                statuses.push((
+                    format!("status_{game_id}"),
+                    (state.status as i32).to_string(),
+                ));
+                messages.extend(handle_wager(deps.storage, &env, state, GameStatus::Active)?);
+                resigned.push(game_id.to_string());
+            }
+            GameStatus::Pending => {
+                messages.extend(cancel_pending(deps.storage, game_id, &state)?);
+                cancelled.push(game_id.to_string());
+            }
+            GameStatus::Ready => {
+                messages.extend(abort_unready(deps.storage, &env, state)?);
+                statuses.push((
+                    format!("status_{game_id}"),
+                    (GameStatus::Aborted as i32).to_string(),
+                ));
+                cancelled.push(game_id.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "resign_many")
+        .add_attribute("resigned", resigned.join(","))
+        .add_attribute("cancelled", cancelled.join(","))
+        .add_attributes(statuses))
+}
+
+fn cancel_game(
+    deps: DepsMut,
+    _env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            if state.status != GameStatus::Pending {
+                return Err(StdError::generic_err(
+                    "Only a game still waiting for an opponent can be cancelled",
+                )
+                .into());
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(StdError::generic_err("Only the creator can cancel this game").into());
+            }
+
+            let refund_messages = cancel_pending(deps.storage, game_id, &state)?;
+            Ok(Response::new()
+                .add_messages(refund_messages)
+                .add_attribute("cancelled", game_id.to_string()))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Anyone may clear a pending game nobody joined before its deadline; the creator is refunded
+fn expire_game(deps: DepsMut, env: Env, game_id: u64) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            if state.status != GameStatus::Pending {
+                return Err(StdError::generic_err(
+                    "Only a game still waiting for an opponent can expire",
+                )
+                .into());
+            }
+            let deadline = state
+                .join_deadline_height
+                .ok_or_else(|| StdError::generic_err("Game has no join deadline"))?;
+            if env.block.height <= deadline {
+                return Err(StdError::generic_err("Join deadline has not passed").into());
+            }
+
+            let refund_messages = cancel_pending(deps.storage, game_id, &state)?;
+            Ok(Response::new()
+                .add_messages(refund_messages)
+                .add_attribute("action", "expire")
+                .add_attribute("game_id", game_id.to_string()))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Removes a pending game and returns the refund of the creator's wager
+fn cancel_pending(
+    storage: &mut dyn Storage,
+    game_id: u64,
+    state: &GameState,
+) -> StdResult<Vec<CosmosMsg>> {
+    GAMES.remove(storage, &game_id)?;
+    unlist_open_game(storage, state)?;
+    update_counters(storage, |counters| {
+        counters.move_game(Some(GameStatus::Pending), None);
+        counters.escrowed = counters.escrowed.saturating_sub(state.wager);
+    })?;
+    let config = CONFIG.load(storage)?;
+
+    let mut messages = Vec::new();
+    if let Some(creator) = state.white.clone().or_else(|| state.black.clone()) {
+        leave_active_game(storage, &creator)?;
+        unindex_player_game(storage, &creator, game_id)?;
+        if state.wager > 0 {
+            messages.push(payout_message(
+                &config,
+                Payout {
+                    recipient: creator,
+                    amount: Uint128::from(state.wager),
+                },
+            )?);
+        }
+    }
+    Ok(messages)
+}
+
+fn offer_draw(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            // An offer from the opponent replaces any offer already on the table
+            state.draw_offered_by = Some(sender);
+            state.draw_offered_at = Some(state.turn);
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::default())
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn accept_draw(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            match &state.draw_offered_by {
+                None if state.draw_offered_at.is_some() => {
+                    return Err(StdError::generic_err("Draw offer has expired").into())
+                }
+                None => return Err(StdError::generic_err("No draw has been offered").into()),
+                Some(offerer) if offerer == &sender => {
+                    return Err(StdError::generic_err("Cannot accept your own draw offer").into())
+                }
+                Some(_) => {}
+            }
+
+            state.status = GameStatus::DrawAgreed;
+            state.draw_offered_by = None;
+            state.draw_offered_at = None;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::default().add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Withdraws a draw offer once more than `draw_offer_plies` plies have been played since it was
+// made. When it was made is kept, so AcceptDraw can say the offer expired rather than never was.
+fn expire_draw_offer(state: &mut GameState, draw_offer_plies: u32) {
+    if let Some(offered_at) = state.draw_offered_at {
+        if state.turn > offered_at + u64::from(draw_offer_plies) {
+            state.draw_offered_by = None;
+        }
+    }
+}
+
+// Aborting is only for games called off before they really got going
+fn ensure_abortable(storage: &dyn Storage, state: &GameState) -> Result<(), ContractError> {
+    if state.status != GameStatus::Active {
+        return Err(ContractError::GameNotActive);
+    }
+    let config = CONFIG.load(storage)?;
+    if state.turn >= u64::from(config.abort_plies) {
+        return Err(StdError::generic_err("Too late to abort").into());
+    }
+    Ok(())
+}
+
+fn offer_abort(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            ensure_abortable(deps.storage, &state)?;
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            state.abort_offered_by = Some(sender);
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::default())
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn accept_abort(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            ensure_abortable(deps.storage, &state)?;
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            match &state.abort_offered_by {
+                None => return Err(StdError::generic_err("No abort has been offered").into()),
+                Some(offerer) if offerer == &sender => {
+                    return Err(StdError::generic_err("Cannot accept your own abort offer").into())
+                }
+                Some(_) => {}
+            }
+
+            state.status = GameStatus::Aborted;
+            state.abort_offered_by = None;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::default().add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// The player to move offers to let their opponent take back the move just played
+fn offer_takeback(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status.is_finished() {
+                return Err(ContractError::GameFinished);
+            }
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            if state.moves.is_empty() {
+                return Err(StdError::generic_err("No move to take back").into());
+            }
+            let side_to_move = if white_to_move(&state.fen) {
+                &state.white
+            } else {
+                &state.black
+            };
+            if side_to_move != &Some(sender.clone()) {
+                return Err(
+                    StdError::generic_err("Only the player to move can offer a takeback").into(),
+                );
+            }
+            state.takeback_offered_by = Some(sender);
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::default())
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// The player who made the last move takes it back, replaying the rest of the game from the start
+fn accept_takeback(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status.is_finished() {
+                return Err(ContractError::GameFinished);
+            }
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            match &state.takeback_offered_by {
+                None => return Err(StdError::generic_err("No takeback has been offered").into()),
+                Some(offerer) if offerer == &sender => {
+                    return Err(
+                        StdError::generic_err("Cannot accept your own takeback offer").into(),
+                    )
+                }
+                Some(_) => {}
+            }
+            let mut moves = state.moves.clone();
+            let undone = moves
+                .pop()
+                .ok_or_else(|| StdError::generic_err("No move to take back"))?;
+
+            // Replaying rebuilds the position and the repetition history together
+            // Each move keeps the block it was first played in
+            let turn = state.turn;
+            let heights = state.move_heights.clone();
+            state.fen = state.start_fen.clone();
+            state.positions = vec![position_key(&state.fen)];
+            state.moves.clear();
+            state.move_heights.clear();
+            for (played, height) in moves.into_iter().zip(heights) {
+                let (move_from, move_to, promotion) =
+                    split_coordinate_move(&played).map_err(StdError::generic_err)?;
+                apply_move(&mut state, move_from, move_to, promotion, height)
+                    .map_err(StdError::generic_err)?;
+            }
+            state.status = GameStatus::Active;
+            state.turn = turn.saturating_sub(1);
+            state.takeback_offered_by = None;
+            // The position the draw was offered in is gone
+            state.draw_offered_by = None;
+            state.draw_offered_at = None;
+            state.premove = None;
+            // The player taking back is on move again, with a fresh clock
+            state.last_move_height = env.block.height;
+            state.last_activity_height = env.block.height;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::new()
+                .add_attribute("action", "takeback")
+                .add_attribute("game_id", game_id.to_string())
+                .add_attribute("move", undone))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn claim_timeout(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            let timeout = state
+                .move_timeout_blocks
+                .ok_or_else(|| StdError::generic_err("Game has no move timeout"))?;
+
+            let white_to_play = white_to_move(&state.fen);
+            let waiting_player = if white_to_play {
+                state.black.clone()
+            } else {
+                state.white.clone()
+            };
+            if waiting_player != Some(sender.clone()) {
+                return Err(StdError::generic_err(
+                    "Only the player waiting for a move can claim a timeout",
+                )
+                .into());
+            }
+            if env.block.height.saturating_sub(state.last_move_height) <= timeout {
+                return Err(StdError::generic_err("Move deadline has not passed").into());
+            }
+
+            state.status = if white_to_play {
+                GameStatus::BlackWins
+            } else {
+                GameStatus::WhiteWins
+            };
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::default().add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Unlike a move timeout this applies to every game, and any player action restarts the count
+fn claim_abandonment(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            // The player who has gone quiet is the one whose move it is
+            let white_to_play = white_to_move(&state.fen);
+            let waiting_player = if white_to_play {
+                state.black.clone()
+            } else {
+                state.white.clone()
+            };
+            if waiting_player != Some(sender.clone()) {
+                return Err(StdError::generic_err(
+                    "Cannot claim abandonment while it is your move",
+                )
+                .into());
+            }
+            let config = CONFIG.load(deps.storage)?;
+            if env.block.height.saturating_sub(state.last_activity_height)
+                <= config.abandonment_blocks
+            {
+                return Err(StdError::generic_err("Game has not been abandoned").into());
+            }
+
+            state.status = if white_to_play {
+                GameStatus::BlackWins
+            } else {
+                GameStatus::WhiteWins
+            };
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::new()
+                .add_attribute("action", "claim_abandonment")
+                .add_attribute("game_id", game_id.to_string())
+                .add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn claim_fifty_move(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            if halfmove_clock(&state.fen) < FIFTY_MOVE_PLIES {
+                return Err(StdError::generic_err("Fifty-move rule cannot be claimed yet").into());
+            }
+
+            state.status = GameStatus::DrawFiftyMove;
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::default().add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Either player can claim a draw by threefold repetition or the fifty-move rule whenever the
+// current position qualifies
+fn claim_draw(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(ContractError::NotAPlayer);
+            }
+            // The current position is always the last one recorded
+            let repetitions = match state.positions.last() {
+                Some(current) => state.positions.iter().filter(|p| *p == current).count(),
+                None => 0,
+            };
+            state.status = if repetitions >= 3 {
+                GameStatus::DrawRepetition
+            } else if halfmove_clock(&state.fen) >= FIFTY_MOVE_PLIES {
+                GameStatus::DrawFiftyMove
+            } else {
+                return Err(StdError::generic_err(
+                    "Neither threefold repetition nor the fifty-move rule applies",
+                )
+                .into());
+            };
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::new()
+                .add_attribute("action", "claim_draw")
+                .add_attribute("game_id", game_id.to_string())
+                .add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn extend_deadline(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    new_deadline: u64,
+) -> Result<Response, ContractError> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status != GameStatus::Pending {
+                return Err(StdError::generic_err("Game is not pending").into());
+            }
+            // A pending game only has its creator seated
+            if state.white != Some(sender.clone()) && state.black != Some(sender.clone()) {
+                return Err(
+                    StdError::generic_err("Only the game creator can extend the deadline").into(),
+                );
+            }
+            let current_deadline = state
+                .join_deadline_height
+                .ok_or_else(|| StdError::generic_err("Game has no join deadline"))?;
+            if new_deadline <= current_deadline {
+                return Err(StdError::generic_err(
+                    "New deadline must be later than the current one",
+                )
+                .into());
+            }
+            if new_deadline > env.block.height + MAX_JOIN_WINDOW_BLOCKS {
+                return Err(StdError::generic_err("Deadline exceeds maximum join window").into());
+            }
+
+            state.join_deadline_height = Some(new_deadline);
+            GAMES.insert(deps.storage, &game_id, &state)?;
+            Ok(Response::new().add_attribute("join_deadline_height", new_deadline.to_string()))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+// Resigning and cancelling stay open while paused, so players can always get out
+fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.paused {
+        return Err(ContractError::Paused);
+    }
+    Ok(())
+}
+
+fn set_paused(deps: DepsMut, sender: Addr, paused: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if sender != config.owner {
+        return Err(StdError::generic_err("Only the owner can pause the contract").into());
+    }
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
+
+// Sends the owner some or all of the fees the contract has accrued
+fn withdraw_fees(
+    deps: DepsMut,
+    sender: Addr,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if sender != config.owner {
+        return Err(StdError::generic_err("Only the owner can withdraw fees").into());
+    }
+    let accrued = ACCRUED_FEES.may_load(deps.storage)?.unwrap_or_default();
+    let amount = amount.unwrap_or(accrued);
+    if amount.is_zero() {
+        return Err(StdError::generic_err("No fees to withdraw").into());
+    }
+    if amount > accrued {
+        return Err(StdError::generic_err("Amount exceeds accrued fees").into());
+    }
+    // Checked above, so this can't underflow
+    ACCRUED_FEES.save(deps.storage, &(accrued - amount))?;
+
+    let message = payout_message(
+        &config,
+        Payout {
+            recipient: config.owner.clone(),
+            amount,
+        },
+    )?;
+    Ok(Response::new()
+        .add_message(message)
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("amount", amount.to_string()))
+}
+
+// Owner only: recreates a game from elsewhere by replaying its moves. Nothing is staked for a
+// seeded game, so it can't carry a wager; the result is recorded like any other.
+fn seed_game(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    white: Addr,
+    black: Addr,
+    moves: Vec<String>,
+    wager: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if sender != config.owner {
+        return Err(StdError::generic_err("Only the owner can seed games").into());
+    }
+    if !wager.unwrap_or_default().is_zero() {
+        return Err(StdError::generic_err(
+            "A seeded game can't carry a wager: nothing was staked for it",
+        )
+        .into());
+    }
+    let white = deps.api.addr_validate(white.as_str())?;
+    let black = deps.api.addr_validate(black.as_str())?;
+    if white == black {
+        return Err(StdError::generic_err("Cannot play yourself").into());
+    }
+
+    let game_id = allocate_game_id(deps.storage)?;
+    let mut game = pending_game(game_id, STARTING_FEN.to_string(), 0, &env);
+    game.status = GameStatus::Active;
+    game.started_height = Some(env.block.height);
+    game.started_time = Some(env.block.time.seconds());
+    // Any illegal move, or one after the game has ended, rejects the whole seed
+    for (ply, played) in moves.iter().enumerate() {
+        if game.status != GameStatus::Active {
+            return Err(
+                StdError::generic_err(format!("Game ended before move {}", ply + 1)).into(),
+            );
+        }
+        let (move_from, move_to, promotion) = split_coordinate_move(played)
+            .map_err(|err| StdError::generic_err(format!("Move {}: {err}", ply + 1)))?;
+        apply_move(&mut game, move_from, move_to, promotion, env.block.height)
+            .map_err(|err| StdError::generic_err(format!("Move {}: {err}", ply + 1)))?;
+        cap_plies(&mut game, config.max_plies);
+    }
+
+    // The owner is recreating a game, so the players' active game caps don't apply
+    for player in [&white, &black] {
+        index_player_game(deps.storage, player, game_id)?;
+        enter_active_game(deps.storage, player, None)?;
+    }
+    game.white = Some(white);
+    game.black = Some(black);
+    GAMES.insert(deps.storage, &game_id, &game)?;
+    update_counters(deps.storage, |counters| {
+        counters.move_game(None, Some(GameStatus::Active))
+    })?;
+
+    let response = Response::new()
+        .add_attribute("action", "seed")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("new_fen", game.fen.clone())
+        .add_attribute("status", (game.status as i32).to_string());
+    // A seeded game that already has a result is settled straight away
+    let wager_messages = handle_wager(deps.storage, &env, game, GameStatus::Active)?;
+    Ok(response.add_messages(wager_messages))
+}
+
+// Owner's safety valve for a game wedged by a client bug: settles it with the given result
+fn admin_resolve(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    game_id: u64,
+    outcome: AdminOutcome,
+) -> Result<Response, ContractError> {
+    if sender != CONFIG.load(deps.storage)?.owner {
+        return Err(StdError::generic_err("Only the owner can resolve games").into());
+    }
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if state.status.is_finished() {
+                return Err(ContractError::GameFinished);
+            }
+            // A pending game has only one stake in it; cancelling is the way out of that
+            if state.status != GameStatus::Active {
+                return Err(ContractError::GameNotActive);
+            }
+            state.status = match outcome {
+                AdminOutcome::WhiteWins => GameStatus::WhiteWins,
+                AdminOutcome::BlackWins => GameStatus::BlackWins,
+                AdminOutcome::Draw => GameStatus::DrawAgreed,
+            };
+            GAMES.insert(deps.storage, &game_id, &state)?;
+
+            let wager_messages = handle_wager(deps.storage, &env, state, GameStatus::Active)?;
+            Ok(Response::new()
+                .add_attribute("action", "admin_resolve")
+                .add_attribute("game_id", game_id.to_string())
+                .add_attribute("admin_resolved", "true")
+                .add_messages(wager_messages))
+        }
+        None => Err(ContractError::GameNotFound { game_id }),
+    }
+}
+
+fn set_player_name(
+    deps: DepsMut,
+    _env: Env,
+    sender: Addr,
+    name: String,
+) -> Result<Response, ContractError> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(StdError::generic_err(format!(
+            "Name must be between 1 and {MAX_NAME_LENGTH} characters"
+        ))
+        .into());
+    }
+    let mut profile = load_profile(deps.storage, &sender);
+    profile.name = Some(name);
+    PLAYERS.insert(deps.storage, &sender, &profile)?;
+    Ok(Response::default())
+}
+
+// Players who never set a name or played a rated game still get a default profile
+fn load_profile(storage: &dyn Storage, address: &Addr) -> PlayerProfile {
+    PLAYERS.get(storage, address).unwrap_or(PlayerProfile {
+        name: None,
+        elo: DEFAULT_ELO,
+    })
+}
+
+// `from` is the status the counters still hold the game under: Active, or Ready for a game called
+// off before it began
+fn handle_wager(
+    storage: &mut dyn Storage,
+    env: &Env,
+    mut game: GameState,
+    from: GameStatus,
+) -> StdResult<Vec<CosmosMsg>> {
+    // A game is settled at most once, whichever path reaches it
+    if !game.status.is_finished() || game.paid {
+        return Ok(Vec::new());
+    }
+    game.paid = true;
+    // Every result passes through here, so this is the one place the winner is recorded
+    game.winner = winning_player(&game);
+    game.ended_height = Some(env.block.height);
+    GAMES.insert(storage, &game.id, &game)?;
+    record_result(storage, &game)?;
+    for player in [&game.white, &game.black].into_iter().flatten() {
+        leave_active_game(storage, player)?;
+    }
+    let config = CONFIG.load(storage)?;
+
+    // Both wagers count towards lifetime volume, whatever the result, and all of it leaves escrow
+    let pot = pot_size(game.wager)?;
+    add_volume(storage, pot)?;
+    update_counters(storage, |counters| {
+        counters.move_game(Some(from), Some(game.status));
+        counters.escrowed = counters.escrowed.saturating_sub(pot.u128());
+    })?;
+
+    // Once paid, only the result is worth keeping. With the game gone from GAMES it can't be
+    // settled again either.
+    if config.archive_finished_games {
+        ARCHIVED_GAMES.insert(
+            storage,
+            &game.id,
+            &GameResult {
+                id: game.id,
+                white: game.white.clone(),
+                black: game.black.clone(),
+                status: game.status,
+                winner: game.winner.clone(),
+            },
+        )?;
+        GAMES.remove(storage, &game.id)?;
+        // The spectator list went with the game, so nobody is left watching it
+        for spectator in &game.spectators {
+            stop_spectating(storage, spectator, game.id)?;
+        }
+    }
+    // Rounds of a match are free games; the match holds the stakes
+    let match_messages = match game.match_id {
+        Some(match_id) => finish_round(storage, env, match_id, &game)?,
+        None => Vec::new(),
+    };
+
+    // The settled game is saved by now, so anything that runs once these messages go out can
+    // only ever find it paid
+    let payouts = compute_payouts(&config, &game)?;
+    let mut messages = payout_messages(storage, &config, payouts)?;
+    messages.extend(match_messages);
+    Ok(messages)
+}
+
+fn add_volume(storage: &mut dyn Storage, pot: Uint128) -> StdResult<()> {
+    let volume = TOTAL_VOLUME.may_load(storage)?.unwrap_or_default();
+    let volume = volume
+        .checked_add(pot)
+        .map_err(|_| StdError::generic_err("Total volume overflow"))?;
+    TOTAL_VOLUME.save(storage, &volume)
+}
+
+// Only called for a game being settled, which the `paid` flag guarantees happens once
+fn record_result(storage: &mut dyn Storage, game: &GameState) -> StdResult<()> {
+    // An aborted game was never really played, so it counts for neither stats nor ratings
+    if game.status == GameStatus::Aborted {
+        return Ok(());
+    }
+    let seats = [(game.white.as_ref(), true), (game.black.as_ref(), false)];
+    for (player, is_white) in seats {
+        let player = match player {
+            Some(player) => player,
+            None => continue,
+        };
+        let mut stats = PLAYER_STATS.get(storage, player).unwrap_or_default();
+        stats.games_played += 1;
+        match game.status.white_won() {
+            Some(white_won) if white_won == is_white => stats.wins += 1,
+            Some(_) => stats.losses += 1,
+            None => stats.draws += 1,
+        }
+        PLAYER_STATS.insert(storage, player, &stats)?;
+    }
+
+    if let (Some(white), Some(black)) = (&game.white, &game.black) {
+        // Resignations and timeouts are ordinary losses; every kind of draw scores half a point
+        let white_score = match game.status.white_won() {
+            Some(true) => 1000,
+            Some(false) => 0,
+            None => 500,
+        };
+        let mut white_profile = load_profile(storage, white);
+        let mut black_profile = load_profile(storage, black);
+        let change = rating_change(white_profile.elo, black_profile.elo, white_score);
+        white_profile.elo += change;
+        black_profile.elo -= change;
+        PLAYERS.insert(storage, white, &white_profile)?;
+        PLAYERS.insert(storage, black, &black_profile)?;
+    }
+    Ok(())
+}
+
+// Turns payouts into transfers. A contract that accrues fees is its own collector, so the
+// collector's share is added to ACCRUED_FEES instead of being sent.
+fn payout_messages(
+    storage: &mut dyn Storage,
+    config: &Config,
+    payouts: Vec<Payout>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = Vec::new();
+    for payout in payouts {
+        if config.accrue_fees && payout.recipient == config.fee_collector {
+            let accrued = ACCRUED_FEES.may_load(storage)?.unwrap_or_default();
+            ACCRUED_FEES.save(storage, &accrued.checked_add(payout.amount)?)?;
+        } else {
+            messages.push(payout_message(config, payout)?);
+        }
+    }
+    Ok(messages)
+}
+
+// Pays out in the wager token when one is configured, and in native uscrt otherwise
+fn payout_message(config: &Config, payout: Payout) -> StdResult<CosmosMsg> {
+    match &config.wager_token {
+        Some(token) => snip20::transfer_msg(
+            payout.recipient.to_string(),
+            payout.amount,
+            None,
+            None,
+            BLOCK_SIZE,
+            token.code_hash.clone(),
+            token.address.to_string(),
+        ),
+        None => Ok(CosmosMsg::Bank(BankMsg::Send {
+            to_address: payout.recipient.to_string(),
+            amount: vec![Coin {
+                denom: "uscrt".to_string(),
+                amount: payout.amount,
+            }],
+        })),
+    }
+}
+
+// Both players' wagers together, or an error instead of a panic if that can't be represented
+fn pot_size(wager: u128) -> StdResult<Uint128> {
+    Uint128::from(wager)
+        .checked_mul(Uint128::from(2u128))
+        .map_err(|_| StdError::generic_err("Wager too large: the pot would overflow"))
+}
+
+// None for draws and for games still in progress
+fn winning_player(game: &GameState) -> Option<Addr> {
+    match game.status.white_won() {
+        Some(true) => game.white.clone(),
+        Some(false) => game.black.clone(),
+        None => None,
+    }
+}
+
+// Works out who is paid what for the game's current status, without touching storage.
+// All pot arithmetic is checked, so a fee of 0 pays the winner exactly both wagers.
+fn compute_payouts(config: &Config, game: &GameState) -> StdResult<Vec<Payout>> {
+    let mut payouts = Vec::new();
+
+    let pot = pot_size(game.wager)?;
+    let amount = match game.status {
+        GameStatus::WhiteWins => pot,
+        GameStatus::BlackWins => pot,
+        GameStatus::WhiteResigned => pot,
+        GameStatus::BlackResigned => pot,
+        _ => Uint128::zero(), // Draws are refunded below, untaxed
+    };
+
+    if game.status.refunds_wagers() {
+        // In a draw or an aborted game, each player gets their wager back
+        if let (Some(white_addr), Some(black_addr)) = (game.white.clone(), game.black.clone()) {
+            if game.wager > 0 {
+                payouts.push(Payout {
+                    recipient: white_addr,
+                    amount: Uint128::from(game.wager),
+                });
+                payouts.push(Payout {
+                    recipient: black_addr,
+                    amount: Uint128::from(game.wager),
+                });
+            }
+        }
+    } else if !amount.is_zero() {
+        if let Some(addr) = winning_player(game) {
+            let referrers = [&game.white_referrer, &game.black_referrer];
+            payouts = decisive_payouts(config, amount, addr, referrers)?;
+        }
+    }
+
+    Ok(payouts)
+}
+
+// Splits a decisive pot between the winner, the players' referrers and the fee collector
+fn decisive_payouts(
+    config: &Config,
+    amount: Uint128,
+    winner: Addr,
+    referrers: [&Option<Addr>; 2],
+) -> StdResult<Vec<Payout>> {
+    let mut payouts = Vec::new();
+    if amount.is_zero() {
+        return Ok(payouts);
+    }
+
+    // Winner takes their share of both wagers, minus the platform fee
+    let fee = amount.multiply_ratio(config.fee_bps, 10_000u128);
+    let net = amount.checked_sub(fee)?;
+    let winner_share = net.multiply_ratio(config.winner_bps, 10_000u128);
+    payouts.push(Payout {
+        recipient: winner,
+        amount: winner_share,
+    });
+
+    // Each player's referrer earns a slice of the fee charged on that player's half of the pot
+    let referral_cut = fee.multiply_ratio(config.referral_bps, 20_000u128);
+    let mut collector_fee = fee;
+    if !referral_cut.is_zero() {
+        for referrer in referrers.into_iter().flatten() {
+            payouts.push(Payout {
+                recipient: referrer.clone(),
+                amount: referral_cut,
+            });
+            collector_fee = collector_fee.checked_sub(referral_cut)?;
+        }
+    }
+
+    // Whatever the winner doesn't keep goes to the collector along with the fee
+    let collector_amount = collector_fee.checked_add(net.checked_sub(winner_share)?)?;
+    if !collector_amount.is_zero() {
+        payouts.push(Payout {
+            recipient: config.fee_collector.clone(),
+            amount: collector_amount,
+        });
+    }
+    Ok(payouts)
+}
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    if let Some(game_id) = query_game_id(&msg) {
+        validate_game_id(deps.storage, game_id)?;
+    }
+    match msg {
+        QueryMsg::GetGame { game_id } => {
+            return get_game_state(deps, env, game_id);
+        }
+        QueryMsg::GetGames { game_ids } => get_games(deps, game_ids),
+        QueryMsg::ListGames {
+            start_after,
+            limit,
+            status,
+        } => {
+            return all_games(deps, env, start_after, limit, status);
+        }
+        QueryMsg::OpenGames {
+            start_after,
+            limit,
+            wager,
+        } => open_games(deps, env, start_after, limit, wager),
+        QueryMsg::SpectatingGames { address, limit } => spectating_games(deps, env, address, limit),
+        QueryMsg::Spectators { game_id } => match GAMES.get(deps.storage, &game_id) {
+            Some(state) => to_binary(&QueryAnswer::Spectators(state.spectators)),
+            None => Err(missing_game(deps.storage, game_id)),
+        },
+        QueryMsg::AllLegalMoves { game_id } => all_legal_moves(deps, env, game_id),
+        QueryMsg::LegalMoves { game_id } => coordinate_legal_moves(deps, env, game_id),
+        QueryMsg::Fen { game_id } => fen(deps, game_id),
+        QueryMsg::Board { game_id } => board(deps, game_id),
+        QueryMsg::Material { game_id } => material(deps, game_id),
+        QueryMsg::Timeline { game_id } => timeline(deps, game_id),
+        QueryMsg::CanJoin { game_id, player } => can_join(deps, env, game_id, player),
+        QueryMsg::Turn { game_id } => turn(deps, env, game_id),
+        QueryMsg::TimeLeft { game_id } => time_left(deps, env, game_id),
+        QueryMsg::PlayerStats { player } => player_stats(deps, player),
+        QueryMsg::Leaderboard { limit } => leaderboard(deps, limit),
+        QueryMsg::Config {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&QueryAnswer::Config {
+                owner: config.owner,
+                paused: config.paused,
+            })
+        }
+        QueryMsg::Version {} => to_binary(&QueryAnswer::Version(CONTRACT_INFO.load(deps.storage)?)),
+        QueryMsg::TotalVolume {} => total_volume(deps, env),
+        QueryMsg::AccruedFees {} => {
+            let accrued = ACCRUED_FEES.may_load(deps.storage)?.unwrap_or_default();
+            to_binary(&QueryAnswer::AccruedFees(accrued))
+        }
+        QueryMsg::Stats {} => stats(deps),
+        QueryMsg::ProjectedSettlement {
+            game_id,
+            hypothetical_status,
+        } => projected_settlement(deps, env, game_id, hypothetical_status),
+        QueryMsg::GameDetail { game_id } => game_detail(deps, env, game_id),
+        QueryMsg::GamesByPlayer {
+            player,
+            start_after,
+            limit,
+        } => games_by_player(deps, env, player, start_after, limit),
+        QueryMsg::ExportPgn { game_id } => export_pgn(deps, env, game_id),
+        QueryMsg::GetMatch { match_id } => match MATCHES.get(deps.storage, &match_id) {
+            Some(state) => to_binary(&QueryAnswer::Match(state)),
+            None => Err(ContractError::MatchNotFound { match_id }.into()),
+        },
+    }
+}
+
+fn get_game_state(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            // Parse the FEN once and derive every extra field from the same board
+            let board = parse_board(&state.fen).map_err(StdError::generic_err)?;
+            let summary = summarize(&board);
+            // `turn` counts plies while the FEN counts full moves, so the two must stay in step
+            // A game that starts with Black to move reaches the next full move after one ply
+            let black_started = !white_to_move(&state.start_fen) as u64;
+            let expected_fullmove =
+                fullmove_number(&state.start_fen) as u64 + (state.turn + black_started) / 2;
+            let fullmove_number = fullmove_number(&state.fen) as u64;
+            let diagnostic = if fullmove_number != expected_fullmove {
+                Some("turn/fullmove desync".to_string())
+            } else {
+                None
+            };
+            Ok(to_binary(&QueryAnswer::GameState(Box::new(GameInfo {
+                status_label: state.status.label().to_string(),
+                game: state,
+                in_check: summary.in_check,
+                legal_move_count: summary.legal_move_count,
+                is_terminal: summary.is_terminal,
+                fullmove_number,
+                diagnostic,
+            })))?)
+        }
+        // Contracts that archive finished games still answer with the result
+        None => match ARCHIVED_GAMES.get(deps.storage, &game_id) {
+            Some(result) => to_binary(&QueryAnswer::ArchivedGame(result)),
+            None => Err(missing_game(deps.storage, game_id)),
+        },
+    }
+}
+
+// Why a by-id query has no game to answer from. An archived game only kept its result, which
+// GetGame returns, so the other queries point there instead of claiming the game never existed.
+fn missing_game(storage: &dyn Storage, game_id: u64) -> StdError {
+    match ARCHIVED_GAMES.get(storage, &game_id) {
+        Some(result) => StdError::generic_err(format!(
+            "Game {game_id} is archived: {}. GetGame returns its result",
+            result.status.label()
+        )),
+        None => ContractError::GameNotFound { game_id }.into(),
+    }
+}
+
+fn all_games(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    status: Option<GameStatus>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let last_id = NEXT_GAME_ID.may_load(deps.storage)?.unwrap_or(0);
+
+    // Keymap iteration order changes as games are removed, so walk the ids instead
+    let mut games: Vec<GameState> = Vec::new();
+    let mut next_start_after = None;
+    let first_id = start_after.unwrap_or(0) + 1;
+    for game_id in first_id..=last_id {
+        // A page also ends after MAX_SCAN ids, so a rare status can't make one query read every
+        // game; the next page resumes after the last id read
+        if games.len() == limit || game_id - first_id == MAX_SCAN as u64 {
+            next_start_after = Some(game_id - 1);
+            break;
+        }
+        // Filtering happens before the limit so a page is full of matching games
+        match GAMES.get(deps.storage, &game_id) {
+            Some(game) if status.is_none() || status == Some(game.status) => games.push(game),
+            _ => {}
+        }
+    }
+
+    Ok(to_binary(&QueryAnswer::AllGames {
+        games,
+        next_start_after,
+    })?)
+}
+
+// The lobby: pending games anyone could still join, optionally only those at a given stake
+fn open_games(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    wager: Option<Uint128>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.unwrap_or(0);
+
+    // Only the lobby index is read, so the rest of the game list never costs anything here
+    let mut game_ids = match wager {
+        Some(wager) => OPEN_GAMES
+            .get(deps.storage, &wager.u128())
+            .unwrap_or_default(),
+        None => {
+            let mut game_ids = Vec::new();
+            for entry in OPEN_GAMES.iter(deps.storage)? {
+                game_ids.extend(entry?.1);
+            }
+            game_ids.sort_unstable();
+            game_ids
+        }
+    };
+    game_ids.retain(|game_id| *game_id > start_after);
+
+    let mut games: Vec<GameState> = Vec::new();
+    let mut next_start_after = None;
+    let mut last_scanned = None;
+    for (scanned, game_id) in game_ids.into_iter().enumerate() {
+        // A page also ends after MAX_SCAN ids, and resumes after the last one read
+        if games.len() == limit || scanned == MAX_SCAN {
+            next_start_after = last_scanned;
+            break;
+        }
+        last_scanned = Some(game_id);
+        let game = match GAMES.get(deps.storage, &game_id) {
+            Some(game) if game.status == GameStatus::Pending => game,
+            _ => continue,
+        };
+        // Games past their join deadline stay indexed until expired, but can't be joined
+        let expired =
+            matches!(game.join_deadline_height, Some(deadline) if env.block.height > deadline);
+        if !expired {
+            games.push(game);
+        }
+    }
+
+    Ok(to_binary(&QueryAnswer::AllGames {
+        games,
+        next_start_after,
+    })?)
+}
+
+fn spectating_games(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let games: Vec<GameState> = SPECTATING
+        .get(deps.storage, &address)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|game_id| GAMES.get(deps.storage, game_id))
+        .take(limit)
+        .collect();
+
+    to_binary(&QueryAnswer::SpectatingGames(games))
+}
+
+fn games_by_player(
+    deps: Deps,
+    _env: Env,
+    player: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.unwrap_or(0);
+
+    let mut games: Vec<GameState> = Vec::new();
+    let mut next_start_after = None;
+    let game_ids = PLAYER_GAMES.get(deps.storage, &player).unwrap_or_default();
+    // Cancelled and expired games are taken out of the index, but skip any missing game anyway
+    for game_id in game_ids
+        .into_iter()
+        .filter(|game_id| *game_id > start_after)
+    {
+        if games.len() == limit {
+            next_start_after = games.last().map(|game| game.id);
+            break;
+        }
+        if let Some(game) = GAMES.get(deps.storage, &game_id) {
+            games.push(game);
+        }
+    }
+
+    to_binary(&QueryAnswer::PlayerGames {
+        games,
+        next_start_after,
+    })
+}
+
+// A finished game has no moves to list, even where its last position would still generate some
+// (a resignation or agreed draw), so the move queries say how it ended instead
+fn ensure_moves_listable(state: &GameState) -> StdResult<()> {
+    if state.status.is_finished() {
+        return Err(StdError::generic_err(format!(
+            "Game already finished: {}",
+            state.status.label()
+        )));
+    }
+    Ok(())
+}
+
+fn all_legal_moves(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            ensure_moves_listable(&state)?;
+            let board = parse_board(&state.fen).map_err(StdError::generic_err)?;
+            let moves: Vec<LegalMove> = legal_moves(&board)
+                .into_iter()
+                .map(|m| LegalMove {
+                    from: m.get_source().to_string(),
+                    to: m.get_dest().to_string(),
+                    promotion: m
+                        .get_promotion()
+                        .map(|piece| promotion_letter(piece).to_string()),
+                })
+                .collect();
+            to_binary(&QueryAnswer::AllLegalMoves(moves))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn get_games(deps: Deps, game_ids: Vec<u64>) -> StdResult<Binary> {
+    if game_ids.len() > MAX_QUERY_BATCH_SIZE {
+        return Err(StdError::generic_err(format!(
+            "Cannot fetch more than {MAX_QUERY_BATCH_SIZE} games at once"
+        )));
+    }
+    // Same order as requested, with None where there is no such game
+    let games = game_ids
+        .iter()
+        .map(|game_id| GAMES.get(deps.storage, game_id))
+        .collect();
+    to_binary(&QueryAnswer::Games(games))
+}
+
+fn coordinate_legal_moves(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            ensure_moves_listable(&state)?;
+            let moves = legal_coordinate_moves(&state.fen).map_err(StdError::generic_err)?;
+            to_binary(&QueryAnswer::LegalMoves(moves))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn fen(deps: Deps, game_id: u64) -> StdResult<Binary> {
+    match GAMES.get(deps.storage, &game_id) {
+        Some(state) => to_binary(&QueryAnswer::Fen(state.fen)),
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn board(deps: Deps, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            let grid = board_grid(&state.fen).map_err(StdError::generic_err)?;
+            to_binary(&QueryAnswer::Board(grid))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn material(deps: Deps, game_id: u64) -> StdResult<Binary> {
+    match GAMES.get(deps.storage, &game_id) {
+        Some(state) => to_binary(&QueryAnswer::Material(material_balance(&state.fen))),
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn can_join(deps: Deps, env: Env, game_id: u64, player: Addr) -> StdResult<Binary> {
+    let eligibility = match GAMES.get(deps.storage, &game_id) {
+        Some(state) => {
+            let config = CONFIG.load(deps.storage)?;
+            join_eligibility(&state, &player, env.block.height, config.max_spectators)
+        }
+        // Only settled games are ever archived
+        None if ARCHIVED_GAMES.contains(deps.storage, &game_id) => JoinEligibility::Finished,
+        None => JoinEligibility::NotFound,
+    };
+    to_binary(&QueryAnswer::CanJoin(eligibility))
+}
+
+fn timeline(deps: Deps, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            let timeline = state
+                .moves
+                .into_iter()
+                .zip(state.move_heights)
+                .map(|(move_coordinate, height)| TimedMove {
+                    move_coordinate,
+                    height,
+                })
+                .collect();
+            to_binary(&QueryAnswer::Timeline(timeline))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn turn(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            let white_turn = white_to_move(&state.fen);
+            let (side_to_move, player) = if white_turn {
+                ("white", state.white)
+            } else {
+                ("black", state.black)
+            };
+            to_binary(&QueryAnswer::Turn(TurnInfo {
+                side_to_move: side_to_move.to_string(),
+                player,
+                in_check: in_check(&state.fen),
+                status: state.status,
+            }))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn time_left(deps: Deps, env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            let (side_to_move, player) = if white_to_move(&state.fen) {
+                ("white", state.white)
+            } else {
+                ("black", state.black)
+            };
+            // Only an active game's clock is running
+            let blocks_left = match (state.status, state.move_timeout_blocks) {
+                (GameStatus::Active, Some(timeout)) => {
+                    let elapsed = env.block.height.saturating_sub(state.last_move_height);
+                    Some(timeout.saturating_sub(elapsed))
+                }
+                _ => None,
+            };
+            to_binary(&QueryAnswer::TimeLeft(ClockInfo {
+                side_to_move: side_to_move.to_string(),
+                player,
+                blocks_left,
+            }))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn player_stats(deps: Deps, player: Addr) -> StdResult<Binary> {
+    let stats: PlayerStats = PLAYER_STATS.get(deps.storage, &player).unwrap_or_default();
+    to_binary(&QueryAnswer::PlayerStats(stats))
+}
+
+fn leaderboard(deps: Deps, limit: Option<u32>) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut players = Vec::new();
+    for entry in PLAYERS.iter(deps.storage)? {
+        let (address, profile) = entry?;
+        players.push(PlayerInfo {
+            address,
+            name: profile.name,
+            elo: profile.elo,
+        });
+    }
+    // Ties are broken by address so the order never depends on storage layout
+    players.sort_by(|a, b| b.elo.cmp(&a.elo).then_with(|| a.address.cmp(&b.address)));
+    players.truncate(limit);
+    to_binary(&QueryAnswer::Leaderboard(players))
+}
+
+fn stats(deps: Deps) -> StdResult<Binary> {
+    let counters = COUNTERS.may_load(deps.storage)?.unwrap_or_default();
+    let games_by_status = GameStatus::ALL
+        .iter()
+        .map(|status| StatusCount {
+            status: *status,
+            count: counters
+                .status_counts
+                .get(*status as usize - 1)
+                .copied()
+                .unwrap_or(0),
+        })
+        .collect();
+    to_binary(&QueryAnswer::Stats(ContractStats {
+        // Ids are handed out in sequence, so the last one is also the number of games created
+        games_created: NEXT_GAME_ID.may_load(deps.storage)?.unwrap_or_default(),
+        games_by_status,
+        escrowed: Uint128::from(counters.escrowed),
+        players: counters.players,
+    }))
+}
+
+fn total_volume(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let volume = TOTAL_VOLUME.may_load(deps.storage)?.unwrap_or_default();
+    to_binary(&QueryAnswer::TotalVolume(volume))
+}
+
+fn projected_settlement(
+    deps: Deps,
+    _env: Env,
+    game_id: u64,
+    hypothetical_status: GameStatus,
+) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(mut state) => {
+            if !hypothetical_status.is_finished() {
+                return Err(StdError::generic_err("Status is not a game result"));
+            }
+            // Only the loaded copy changes - nothing is written back
+            state.status = hypothetical_status;
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&QueryAnswer::ProjectedSettlement(compute_payouts(
+                &config, &state,
+            )?))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+fn game_detail(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            let board = parse_board(&state.fen).map_err(StdError::generic_err)?;
+            let summary = summarize(&board);
+            let player_info = |seat: &Option<Addr>| {
+                seat.as_ref().map(|address| {
+                    let profile = load_profile(deps.storage, address);
+                    PlayerInfo {
+                        address: address.clone(),
+                        name: profile.name,
+                        elo: profile.elo,
+                    }
+                })
+            };
+
+            let config = CONFIG.load(deps.storage)?;
+            let projection = |status: GameStatus| {
+                let mut hypothetical = state.clone();
+                hypothetical.status = status;
+                compute_payouts(&config, &hypothetical)
+            };
+            let settlement = SettlementProjection {
+                white_wins: projection(GameStatus::WhiteWins)?,
+                black_wins: projection(GameStatus::BlackWins)?,
+                draw: projection(GameStatus::Stalemate)?,
+            };
+
+            to_binary(&QueryAnswer::GameDetail(Box::new(GameDetail {
+                in_check: summary.in_check,
+                legal_move_count: summary.legal_move_count,
+                is_terminal: summary.is_terminal,
+                last_move: state.moves.last().cloned(),
+                white_player: player_info(&state.white),
+                black_player: player_info(&state.black),
+                settlement,
+                game: state,
+            })))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+// PGN's YYYY.MM.DD for a block time in seconds since the Unix epoch (UTC)
+fn pgn_date(seconds: u64) -> String {
+    // Civil-from-days conversion, with years starting on 1 March so leap days fall last
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+// PGN game termination marker for a status; "*" while the game is still being played
+fn pgn_result(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::WhiteWins | GameStatus::BlackResigned => "1-0",
+        GameStatus::BlackWins | GameStatus::WhiteResigned => "0-1",
+        status if status.is_draw() => "1/2-1/2",
+        _ => "*",
+    }
+}
+
+fn export_pgn(deps: Deps, _env: Env, game_id: u64) -> StdResult<Binary> {
+    let game_state = GAMES.get(deps.storage, &game_id);
+    match game_state {
+        Some(state) => {
+            if state.moves.is_empty() {
+                return Err(StdError::generic_err("Game has no recorded moves"));
+            }
+            let sans = san_moves(&state.start_fen, &state.moves).map_err(StdError::generic_err)?;
+            let result = pgn_result(state.status);
+            let player = |seat: &Option<Addr>| {
+                seat.as_ref()
+                    .map_or_else(|| "?".to_string(), |addr| addr.to_string())
+            };
+
+            // Seven-tag roster, dated by the block the game started in
+            let date = state
+                .started_time
+                .map_or_else(|| "????.??.??".to_string(), pgn_date);
+            let mut pgn = String::new();
+            for (tag, value) in [
+                ("Event", format!("Secret Chess game {game_id}")),
+                ("Site", "Secret Network".to_string()),
+                ("Date", date),
+                ("Round", "-".to_string()),
+                ("White", player(&state.white)),
+                ("Black", player(&state.black)),
+                ("Result", result.to_string()),
+            ] {
+                pgn.push_str(&format!("[{tag} \"{value}\"]\n"));
+            }
+            if state.variant.as_deref() == Some("chess960") {
+                pgn.push_str("[Variant \"Chess960\"]\n");
+            }
+            if state.start_fen != STARTING_FEN {
+                pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", state.start_fen));
+            }
+            pgn.push('\n');
+
+            // Movetext, wrapped to stay within 80 columns
+            let mut tokens = Vec::new();
+            let mut move_number = fullmove_number(&state.start_fen);
+            let mut white_moving = white_to_move(&state.start_fen);
+            for (ply, san) in sans.into_iter().enumerate() {
+                if white_moving {
+                    tokens.push(format!("{move_number}."));
+                } else if ply == 0 {
+                    // A game starting with Black to move opens with e.g. "12..."
+                    tokens.push(format!("{move_number}..."));
+                }
+                if !white_moving {
+                    move_number += 1;
+                }
+                white_moving = !white_moving;
+                tokens.push(san);
+            }
+            tokens.push(result.to_string());
+            let mut line_length = 0;
+            for token in tokens {
+                if line_length > 0 && line_length + 1 + token.len() > 80 {
+                    pgn.push('\n');
+                    line_length = 0;
+                } else if line_length > 0 {
+                    pgn.push(' ');
+                    line_length += 1;
+                }
+                line_length += token.len();
+                pgn.push_str(&token);
+            }
+            pgn.push('\n');
+
+            to_binary(&QueryAnswer::Pgn(pgn))
+        }
+        None => Err(missing_game(deps.storage, game_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::TokenInfo;
+    use crate::state::{ConfigV1, PremoveV1};
+    use cosmwasm_std::coins;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    fn addr(name: &str) -> Addr {
+        Addr::unchecked(name)
+    }
+
+    #[test]
+    fn migrating_from_v0_keeps_turn_and_move_number_in_step() {
+        let mut deps = mock_dependencies();
+        let legacy = GameStateV0 {
+            id: 1,
+            // After 1.e4 e5 2.Nf3, with the "0 1" counters every version 0 FEN had
+            fen: "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 1".to_string(),
+            white: Some(addr("white")),
+            black: Some(addr("black")),
+            turn: 3,
+            status: GameStatus::Active,
+            wager: 1000,
+        };
+        GAMES_V0.insert(&mut deps.storage, &1, &legacy).unwrap();
+        NEXT_GAME_ID.save(&mut deps.storage, &1).unwrap();
+
+        let msg = MigrateMsg {
+            owner: Some("owner".to_string()),
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+        assert_eq!(
+            STATE_VERSION.load(&deps.storage).unwrap(),
+            CURRENT_STATE_VERSION
+        );
+        let game = GAMES.get(&deps.storage, &1).unwrap();
+        assert_eq!(
+            game.fen,
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 2"
+        );
+        assert_eq!(game.start_fen, game.fen);
+        assert_eq!(game.turn, 0);
+        let answer = query(deps.as_ref(), mock_env(), QueryMsg::GetGame { game_id: 1 }).unwrap();
+        match from_binary(&answer).unwrap() {
+            QueryAnswer::GameState(info) => {
+                assert_eq!(info.fullmove_number, 2);
+                assert_eq!(info.diagnostic, None);
+            }
+            other => panic!("Unexpected answer {other:?}"),
+        }
+        let counters = COUNTERS.load(&deps.storage).unwrap();
+        assert_eq!(counters.escrowed, 2000);
+        assert_eq!(counters.players, 2);
+    }
+
+    #[test]
+    fn migrating_from_v1_rewrites_games_and_config() {
+        let mut deps = mock_dependencies();
+        let config = ConfigV1 {
+            fee_bps: 250,
+            fee_collector: addr("collector"),
+            referral_bps: 0,
+            max_spectators: 10,
+            wager_token: None,
+            owner: addr("owner"),
+            paused: false,
+            min_wager: 100,
+            max_wager: None,
+            abandonment_blocks: 500,
+        };
+        CONFIG_V1.save(&mut deps.storage, &config).unwrap();
+        let game = |id: u64, status: GameStatus| GameStateV1 {
+            id,
+            fen: STARTING_FEN.to_string(),
+            white: Some(addr("white")),
+            black: None,
+            turn: 0,
+            status,
+            wager: 500,
+            join_deadline_height: None,
+            spectators: Vec::new(),
+            white_referrer: None,
+            black_referrer: None,
+            premove: None,
+            moves: Vec::new(),
+            positions: Vec::new(),
+            start_fen: STARTING_FEN.to_string(),
+            draw_offered_by: None,
+            last_move_height: 10,
+            last_activity_height: 10,
+            move_timeout_blocks: None,
+            paid: false,
+            invited: None,
+            winner: None,
+            created_height: 10,
+            started_height: None,
+            started_time: None,
+            ended_height: None,
+        };
+        let pending = game(1, GameStatus::Pending);
+        let mut active = game(2, GameStatus::Active);
+        active.black = Some(addr("black"));
+        active.fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_string();
+        active.turn = 1;
+        active.moves = vec!["e2e4".to_string()];
+        active.draw_offered_by = Some(addr("white"));
+        active.premove = Some(PremoveV1 {
+            player: addr("white"),
+            move_from: "e4".to_string(),
+            move_to: "e5".to_string(),
+            promotion: Some("Q".to_string()),
+        });
+        GAMES_V1.insert(&mut deps.storage, &1, &pending).unwrap();
+        GAMES_V1.insert(&mut deps.storage, &2, &active).unwrap();
+        PLAYER_GAMES
+            .insert(&mut deps.storage, &addr("white"), &vec![1, 2])
+            .unwrap();
+        PLAYER_GAMES
+            .insert(&mut deps.storage, &addr("black"), &vec![2])
+            .unwrap();
+        NEXT_GAME_ID.save(&mut deps.storage, &2).unwrap();
+        STATE_VERSION.save(&mut deps.storage, &1).unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+
+        assert_eq!(
+            STATE_VERSION.load(&deps.storage).unwrap(),
+            CURRENT_STATE_VERSION
+        );
+        let migrated_config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(migrated_config.fee_bps, 250);
+        assert_eq!(migrated_config.abandonment_blocks, 500);
+        assert_eq!(migrated_config.winner_bps, 10_000);
+        let migrated = GAMES.get(&deps.storage, &2).unwrap();
+        assert_eq!(migrated.moves, ["e2e4"]);
+        assert_eq!(migrated.move_heights, [10]);
+        assert_eq!(migrated.draw_offered_at, Some(1));
+        assert_eq!(migrated.premove.unwrap().promotion, Some(Promotion::Queen));
+        assert_eq!(OPEN_GAMES.get(&deps.storage, &500), Some(vec![1]));
+        assert_eq!(ACTIVE_GAMES.get(&deps.storage, &addr("white")), Some(2));
+        let counters = COUNTERS.load(&deps.storage).unwrap();
+        assert_eq!(counters.escrowed, 1500);
+        assert_eq!(counters.players, 2);
+    }
+
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            fee_bps: None,
+            fee_collector: None,
+            referral_bps: None,
+            max_spectators: None,
+            wager_token: None,
+            owner: None,
+            min_wager: None,
+            max_wager: None,
+            abandonment_blocks: None,
+            winner_bps: None,
+            max_active_games_per_player: None,
+            archive_finished_games: None,
+            abort_plies: None,
+            max_plies: None,
+            draw_offer_plies: None,
+            accrue_fees: None,
+        }
+    }
+
+    fn instantiate_default(deps: DepsMut) {
+        let msg = default_instantiate_msg();
+        instantiate(deps, mock_env(), mock_info("owner", &[]), msg).unwrap();
+    }
+
+    fn join_game_msg(game_id: u64) -> ExecuteMsg {
+        ExecuteMsg::JoinGame {
+            game_id,
+            referrer: None,
+        }
+    }
+
+    fn create_game_msg() -> ExecuteMsg {
+        ExecuteMsg::CreateGame {
+            join_deadline_blocks: None,
+            referrer: None,
+            move_timeout_blocks: None,
+            fen: None,
+            preferred_color: Some("white".to_string()),
+            opponent: None,
+            variant: None,
+            require_ready: None,
+        }
+    }
+
+    #[test]
+    fn creating_a_game_without_the_id_counter_asks_for_initialization() {
+        let mut deps = mock_dependencies();
+        instantiate_default(deps.as_mut());
+        // As a migration that forgot the counter would leave it
+        NEXT_GAME_ID.remove(&mut deps.storage);
+
+        let info = mock_info("white", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, create_game_msg()).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Contract not initialized");
+    }
+
+    #[test]
+    fn wagers_whose_pot_overflows_are_refused() {
+        assert_eq!(pot_size(u128::MAX / 2).unwrap().u128(), u128::MAX - 1);
+        assert!(pot_size(u128::MAX / 2 + 1).is_err());
+
+        let mut deps = mock_dependencies();
+        instantiate_default(deps.as_mut());
+        let info = mock_info("white", &coins(u128::MAX / 2 + 1, "uscrt"));
+        let err = execute(deps.as_mut(), mock_env(), info, create_game_msg()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Wager too large: the pot would overflow"
+        );
+        assert_eq!(NEXT_GAME_ID.load(&deps.storage).unwrap(), 0);
+    }
+
+    #[test]
+    fn creating_or_joining_with_a_second_coin_is_refused() {
+        let mut deps = mock_dependencies();
+        instantiate_default(deps.as_mut());
+        let two_coins = [Coin::new(1000, "uscrt"), Coin::new(5, "uatom")];
+
+        let info = mock_info("white", &two_coins);
+        let err = execute(deps.as_mut(), mock_env(), info, create_game_msg()).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Send only uscrt");
+
+        let info = mock_info("white", &coins(1000, "uscrt"));
+        execute(deps.as_mut(), mock_env(), info, create_game_msg()).unwrap();
+        let join = ExecuteMsg::JoinGame {
+            game_id: 1,
+            referrer: None,
+        };
+        let info = mock_info("black", &two_coins);
+        let err = execute(deps.as_mut(), mock_env(), info, join).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Send only uscrt");
+        assert_eq!(GAMES.get(&deps.storage, &1).unwrap().black, None);
+    }
+
+    #[test]
+    fn reconnecting_or_spectating_with_funds_is_refused() {
+        let mut deps = mock_dependencies();
+        instantiate_default(deps.as_mut());
+        let info = mock_info("white", &coins(1000, "uscrt"));
+        execute(deps.as_mut(), mock_env(), info, create_game_msg()).unwrap();
+        let info = mock_info("black", &coins(1000, "uscrt"));
+        execute(deps.as_mut(), mock_env(), info, join_game_msg(1)).unwrap();
+
+        for sender in ["white", "spectator"] {
+            let info = mock_info(sender, &coins(5, "uscrt"));
+            let err = execute(deps.as_mut(), mock_env(), info, join_game_msg(1)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Generic error: Spectators and reconnecting players must not send funds"
+            );
+        }
+        // Without funds both are let in
+        for sender in ["white", "spectator"] {
+            let info = mock_info(sender, &[]);
+            execute(deps.as_mut(), mock_env(), info, join_game_msg(1)).unwrap();
+        }
+        assert_eq!(COUNTERS.load(&deps.storage).unwrap().escrowed, 2000);
+    }
+
+    #[test]
+    fn reconnecting_or_spectating_through_the_wager_token_is_refused() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.wager_token = Some(TokenInfo {
+            address: "token".to_string(),
+            code_hash: "hash".to_string(),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let send = |from: &str, amount: u128, msg: &ReceiveMsg| ExecuteMsg::Receive {
+            sender: addr(from),
+            from: addr(from),
+            amount: Uint128::from(amount),
+            msg: Some(to_binary(msg).unwrap()),
+        };
+        let create = ReceiveMsg::CreateGame {
+            join_deadline_blocks: None,
+            referrer: None,
+            move_timeout_blocks: None,
+            fen: None,
+            preferred_color: Some("white".to_string()),
+            opponent: None,
+            variant: None,
+            require_ready: None,
+        };
+        let join = ReceiveMsg::JoinGame {
+            game_id: 1,
+            referrer: None,
+        };
+        let token = || mock_info("token", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            token(),
+            send("white", 1000, &create),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            token(),
+            send("black", 1000, &join),
+        )
+        .unwrap();
+
+        for sender in ["white", "spectator"] {
+            let err =
+                execute(deps.as_mut(), mock_env(), token(), send(sender, 5, &join)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Generic error: Spectators and reconnecting players must not send funds"
+            );
+        }
+        assert_eq!(COUNTERS.load(&deps.storage).unwrap().escrowed, 2000);
+    }
+
+    #[test]
+    fn leaving_before_ready_moves_the_game_from_ready_to_aborted() {
+        let mut deps = mock_dependencies();
+        instantiate_default(deps.as_mut());
+        let create = ExecuteMsg::CreateGame {
+            join_deadline_blocks: None,
+            referrer: None,
+            move_timeout_blocks: None,
+            fen: None,
+            preferred_color: Some("white".to_string()),
+            opponent: None,
+            variant: None,
+            require_ready: Some(true),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("white", &[]), create).unwrap();
+        let info = mock_info("black", &[]);
+        execute(deps.as_mut(), mock_env(), info, join_game_msg(1)).unwrap();
+        let resign = ExecuteMsg::Resign { game_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("white", &[]), resign).unwrap();
+
+        let counts = COUNTERS.load(&deps.storage).unwrap().status_counts;
+        let count = |status: GameStatus| counts[status as usize - 1];
+        assert_eq!(count(GameStatus::Ready), 0);
+        assert_eq!(count(GameStatus::Active), 0);
+        assert_eq!(count(GameStatus::Aborted), 1);
+    }
+
+    #[test]
+    fn migrating_from_a_newer_version_is_refused() {
+        let mut deps = mock_dependencies();
+        STATE_VERSION
+            .save(&mut deps.storage, &(CURRENT_STATE_VERSION + 1))
+            .unwrap();
+        assert!(migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).is_err());
+    }
 }
@@ -0,0 +1,45 @@
+// Integer Elo, so every node computes the same ratings
+
+pub const K_FACTOR: i32 = 32;
+
+// Rating gaps beyond this are scored as if they were this wide
+const MAX_GAP: i32 = 800;
+const GAP_STEP: i32 = 25;
+
+// Expected score of the lower-rated player, in thousandths, for gaps of 0, 25, 50, ... 800
+// points: round(1000 / (1 + 10^(gap / 400)))
+const WEAKER_EXPECTED: [i32; 33] = [
+    500, 464, 429, 394, 360, 327, 297, 267, 240, 215, 192, 170, 151, 133, 118, 104, 91, 80, 70, 61,
+    53, 46, 40, 35, 31, 27, 23, 20, 17, 15, 13, 11, 10,
+];
+
+// Expected score against `opponent`, in thousandths, interpolating between table entries
+pub fn expected_score(rating: i32, opponent: i32) -> i32 {
+    let gap = (opponent - rating).abs().min(MAX_GAP);
+    let index = (gap / GAP_STEP) as usize;
+    let remainder = gap % GAP_STEP;
+    let weaker = match WEAKER_EXPECTED.get(index + 1) {
+        Some(next) => {
+            WEAKER_EXPECTED[index] - (WEAKER_EXPECTED[index] - next) * remainder / GAP_STEP
+        }
+        None => WEAKER_EXPECTED[index],
+    };
+    if rating <= opponent {
+        weaker
+    } else {
+        1000 - weaker
+    }
+}
+
+// Rating points gained (or lost, if negative) for scoring `score` thousandths against
+// `opponent`: 1000 for a win, 500 for a draw, 0 for a loss. The opponent's change is exactly
+// the negation, since their expected score is the complement of this one.
+pub fn rating_change(rating: i32, opponent: i32, score: i32) -> i32 {
+    let scaled = K_FACTOR * (score - expected_score(rating, opponent));
+    // Round half away from zero so the result doesn't depend on whose side is computed
+    if scaled >= 0 {
+        (scaled + 500) / 1000
+    } else {
+        -((-scaled + 500) / 1000)
+    }
+}
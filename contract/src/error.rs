@@ -0,0 +1,55 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+// What an execute handler can fail with. Looking up a game or match, the seat and turn checks and
+// the active-game check have their own variants; a rule only one handler enforces stays a StdError
+// with its own message. The messages are the ones clients already match on.
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Invalid game id {game_id}")]
+    InvalidGameId { game_id: u64 }, // 0, or above any id handed out so far
+
+    #[error("No game found with id {game_id}")]
+    GameNotFound { game_id: u64 },
+
+    #[error("No match found with id {match_id}")]
+    MatchNotFound { match_id: u64 },
+
+    #[error("Not a player")]
+    NotAPlayer,
+
+    #[error("It is {side_to_move}s turn")]
+    NotYourTurn { side_to_move: &'static str }, // "white" or "black"
+
+    #[error("{0}")]
+    IllegalMove(&'static str), // The reason chess.rs gave, e.g. "Illegal move"
+
+    #[error("Wager too low")]
+    WagerTooLow,
+
+    #[error("Wager too high")]
+    WagerTooHigh,
+
+    #[error("Game already finished")]
+    GameFinished,
+
+    #[error("Game is not active")]
+    GameNotActive,
+
+    #[error("Contract is paused")]
+    Paused,
+}
+
+// For queries, which still answer with a StdError
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            // Already a StdError, so it goes out untouched rather than wrapped in another
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
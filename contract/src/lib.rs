@@ -1,4 +1,6 @@
 pub mod contract;
+pub mod error;
 pub mod msg;
 pub mod state;
 pub mod chess;
+pub mod elo;
@@ -1,31 +1,297 @@
+use cosmwasm_std::{Addr, Binary, Uint128};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
-use crate::state::{GameState};
+use crate::state::{
+    ContractVersion, GameResult, GameState, GameStatus, MatchState, PlayerStats, Promotion,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    pub fee_bps: Option<u16>,
+    pub fee_collector: Option<String>, // Defaults to the instantiator
+    pub referral_bps: Option<u16>,
+    pub max_spectators: Option<u32>,   // Defaults to DEFAULT_MAX_SPECTATORS
+    pub wager_token: Option<TokenInfo>, // Take wagers in this SNIP-20 instead of uscrt
+    pub owner: Option<String>,         // Can pause the contract; defaults to the instantiator
+    pub min_wager: Option<Uint128>,    // Smallest wager a game can be created with; 0 if unset
+    pub max_wager: Option<Uint128>,    // No upper bound if unset
+    pub abandonment_blocks: Option<u64>, // Defaults to DEFAULT_ABANDONMENT_BLOCKS
+    pub winner_bps: Option<u16>,       // Winner's share of the pot after fees; 10000 if unset
+    pub max_active_games_per_player: Option<u32>, // Pending or active games a player may sit in
+    pub archive_finished_games: Option<bool>, // Keep only the result of settled games
+    pub abort_plies: Option<u32>,      // Defaults to DEFAULT_ABORT_PLIES
+    pub max_plies: Option<u32>,        // Longest a game may run before it is drawn; no cap if unset
+    pub draw_offer_plies: Option<u32>, // Plies a draw offer stays open for; 0 if unset
+    pub accrue_fees: Option<bool>,     // Hold fees in the contract instead of paying fee_collector
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub owner: Option<String>, // Required when migrating from version 0, which had no owner
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfo {
+    pub address: String,
+    pub code_hash: String,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    CreateGame {},
-    JoinGame   { game_id: u64 },
-    MakeMove   { game_id: u64, move_from: String, move_to: String, promotion: Option<String> }, // e.g., "e2", "e4", "None"
+    CreateGame {
+        join_deadline_blocks: Option<u64>, // Number of blocks the game stays open for an opponent
+        referrer: Option<Addr>,
+        move_timeout_blocks: Option<u64>,
+        fen: Option<String>,               // Custom starting position; the standard one if unset
+        preferred_color: Option<String>,   // "white", "black" or "random" (the default)
+        opponent: Option<Addr>,            // Only this address may join
+        variant: Option<String>,           // "standard" (the default) or "chess960"
+        require_ready: Option<bool>,       // Both players must send Ready before the first move
+    },
+    JoinGame   { game_id: u64, referrer: Option<Addr> },
+    QuickMatch {}, // Joins the oldest open game at the sent wager, or opens one
+    MakeMove   { game_id: u64, move_from: String, move_to: String, promotion: Option<Promotion> }, // e.g., "e2", "e4", "None"
+    Ready      { game_id: u64 },    // require_ready games start once both players have sent it
     Resign     { game_id: u64 },
+    ExtendDeadline { game_id: u64, new_deadline: u64 }, // Block height
+    LeaveGame  { game_id: u64 }, // Stop spectating
+    ClaimFiftyMove { game_id: u64 },
+    ResignMany { game_ids: Vec<u64> }, // Resigns active games and cancels pending ones
+    SetPremove { game_id: u64, move_from: String, move_to: String, promotion: Option<Promotion> }, // Played automatically after the opponent's move
+    SetPlayerName { name: String }, // Shown next to the sender's address in game details
+    CancelGame { game_id: u64 },    // Creator withdraws a game nobody has joined yet
+    ExpireGame { game_id: u64 },    // Anyone clears a pending game whose join deadline has passed
+    OfferDraw  { game_id: u64 },
+    AcceptDraw { game_id: u64 },    // Only the player who didn't make the offer can accept
+    ClaimTimeout { game_id: u64 },  // The waiting player wins once the side to move runs out of blocks
+    ClaimAbandonment { game_id: u64 }, // Waiting player wins after abandonment_blocks of inactivity
+    OfferTakeback  { game_id: u64 }, // By the player to move, to let the opponent undo their move
+    AcceptTakeback { game_id: u64 }, // By the player who made the last move, which is undone
+    ClaimDraw  { game_id: u64 },    // Threefold repetition or fifty-move rule, by either player
+    OfferAbort  { game_id: u64 },   // Only within the first abort_plies plies
+    AcceptAbort { game_id: u64 },   // By the other player; both wagers are refunded unrated
+    Rematch    { game_id: u64 },    // Either player of a finished game re-stakes the wager with colours swapped
+    SetPaused  { paused: bool },    // Owner only; stops new games, joins and moves, not exits
+    AdminResolve { game_id: u64, outcome: AdminOutcome }, // Owner only; settles a stuck active game
+    WithdrawFees { amount: Option<Uint128> }, // Owner only; all accrued fees when amount is unset
+    // Owner only; replays coordinate moves (e.g. "e2e4") into a new game between the two players.
+    // Seeded games are free, so wager must be zero or unset.
+    SeedGame { white: Addr, black: Addr, moves: Vec<String>, wager: Option<Uint128> },
+    CreateMatch { best_of: u8 },    // First to win a majority of best_of games takes both wagers
+    JoinMatch  { match_id: u64 },   // Stakes the same wager and starts the first game
+    CancelMatch { match_id: u64 },  // Creator withdraws a match nobody has joined yet
+    Receive { sender: Addr, from: Addr, amount: Uint128, msg: Option<Binary> }, // SNIP-20 Send callback carrying a ReceiveMsg
+}
+
+// Result the owner can settle a stuck game with. A draw is recorded as DrawAgreed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminOutcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+// Game actions that can be paid for by sending the wager token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    CreateGame {
+        join_deadline_blocks: Option<u64>,
+        referrer: Option<Addr>,
+        move_timeout_blocks: Option<u64>,
+        fen: Option<String>,
+        preferred_color: Option<String>,
+        opponent: Option<Addr>,
+        variant: Option<String>,
+        require_ready: Option<bool>,
+    },
+    JoinGame { game_id: u64, referrer: Option<Addr> },
+    QuickMatch {},
+    Rematch { game_id: u64 },
+    CreateMatch { best_of: u8 },
+    JoinMatch { match_id: u64 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetGame { game_id: u64 },
-    ListGames {}
+    GetGames { game_ids: Vec<u64> }, // At most MAX_QUERY_BATCH_SIZE ids
+    ListGames {
+        start_after: Option<u64>, // Pages through games in id order
+        limit: Option<u32>,
+        status: Option<GameStatus>, // Only games in this state
+    },
+    OpenGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        wager: Option<Uint128>, // Only games staked at exactly this amount
+    },
+    SpectatingGames { address: String, limit: Option<u32> },
+    Spectators { game_id: u64 }, // Who is watching, in the order they joined
+    Stats {},
+    AllLegalMoves { game_id: u64 },
+    LegalMoves { game_id: u64 },
+    Fen { game_id: u64 }, // Just the position, for clients that only draw the board
+    Board { game_id: u64 },
+    Material { game_id: u64 }, // Who is up material, in pawns
+    Timeline { game_id: u64 },
+    CanJoin { game_id: u64, player: Addr },
+    Turn { game_id: u64 },
+    TimeLeft { game_id: u64 },
+    PlayerStats { player: Addr },
+    Leaderboard { limit: Option<u32> }, // Highest rated first
+    Config {},
+    Version {}, // Which contract and version this instance runs
+    TotalVolume {},
+    AccruedFees {},
+    ProjectedSettlement { game_id: u64, hypothetical_status: GameStatus }, // What settling now with this result would pay out
+    GameDetail { game_id: u64 },
+    GamesByPlayer { player: Addr, start_after: Option<u64>, limit: Option<u32> },
+    ExportPgn  { game_id: u64 },
+    GetMatch   { match_id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegalMove {
+    pub from: String,              // e.g. "e7"
+    pub to: String,                // e.g. "e8"
+    pub promotion: Option<String>, // e.g. "q"
+}
+
+// Cheap to poll: who has to move and whether the game is still going
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TurnInfo {
+    pub side_to_move: String,  // "white" or "black"
+    pub player: Option<Addr>,  // None until that seat is taken
+    pub in_check: bool,
+    pub status: GameStatus,
+}
+
+// What JoinGame would do for an address right now
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinEligibility {
+    Joinable { wager: Uint128 }, // Takes the open seat by staking exactly this much
+    AlreadyPlayer,               // Has a seat already; reconnects once the game has started
+    Spectate,                    // Both seats are taken, so joining means watching
+    Full,                        // Both seats and every spectator slot are taken
+    Private,                     // The open seat is reserved for someone else
+    Expired,                     // The join deadline has passed
+    Finished,
+    NotFound,
+}
+
+// A ply of the game and the block it was played in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TimedMove {
+    pub move_coordinate: String, // As in `GameState.moves`, e.g. "e2e4"
+    pub height: u64,
+}
+
+// Whose clock is running and how long they have left to move
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClockInfo {
+    pub side_to_move: String, // "white" or "black"
+    pub player: Option<Addr>,
+    // None when the game has no move timeout or isn't active. At 0 the opponent can claim the
+    // timeout from the next block on.
+    pub blocks_left: Option<u64>,
+}
+
+// GameState plus fields derived from its FEN
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameInfo {
+    pub game: GameState,
+    pub status_label: String,   // game.status by name, e.g. "active" or "white_wins"
+    pub in_check: bool,         // Side to move is in check
+    pub legal_move_count: u32,  // Number of legal moves for the side to move
+    pub is_terminal: bool,      // No legal moves left (checkmate or stalemate)
+    pub fullmove_number: u64,   // From the FEN, should stay in step with turn
+    pub diagnostic: Option<String>,
+}
+
+// Contract-wide totals for dashboards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractStats {
+    pub games_created: u64,
+    pub games_by_status: Vec<StatusCount>,
+    pub escrowed: Uint128, // Stakes held for unsettled games and matches
+    pub players: u64,      // Distinct addresses that have had a seat in a game
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatusCount {
+    pub status: GameStatus,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub recipient: Addr,
+    pub amount: Uint128, // uscrt
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayerInfo {
+    pub address: Addr,
+    pub name: Option<String>,
+    pub elo: i32,
+}
+
+// Payouts for each way the game could still end
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettlementProjection {
+    pub white_wins: Vec<Payout>,
+    pub black_wins: Vec<Payout>,
+    pub draw: Vec<Payout>,
+}
+
+// Everything a game page needs, in one query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameDetail {
+    pub game: GameState,
+    pub in_check: bool,
+    pub legal_move_count: u32,
+    pub is_terminal: bool,
+    pub last_move: Option<String>,
+    pub white_player: Option<PlayerInfo>,
+    pub black_player: Option<PlayerInfo>,
+    pub settlement: SettlementProjection,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryAnswer {
-    GameState(GameState),
-    AllGames(Vec<GameState>)
+    GameState(Box<GameInfo>),
+    Games(Vec<Option<GameState>>),
+    ArchivedGame(GameResult), // GetGame answer for a game archived after settling
+    AllGames { games: Vec<GameState>, next_start_after: Option<u64> }, // Pass the cursor back as start_after for the next page
+    SpectatingGames(Vec<GameState>),
+    Spectators(Vec<Addr>),
+    Stats(ContractStats),
+    AllLegalMoves(Vec<LegalMove>),
+    Turn(TurnInfo),
+    TimeLeft(ClockInfo),
+    PlayerStats(PlayerStats),
+    Leaderboard(Vec<PlayerInfo>),
+    Config { owner: Addr, paused: bool },
+    Version(ContractVersion),
+    LegalMoves(Vec<String>), // Coordinate notation, e.g. "e2e4" or "e7e8q"
+    Fen(String),
+    Board(Vec<Vec<String>>), // Rank 8 first, a-file first; "wP", "bK", ... or "" when empty
+    Material(i32), // White minus Black, so positive means White is ahead
+    Timeline(Vec<TimedMove>), // One entry per ply, in the order played
+    CanJoin(JoinEligibility),
+    TotalVolume(Uint128),
+    AccruedFees(Uint128),
+    ProjectedSettlement(Vec<Payout>),
+    GameDetail(Box<GameDetail>),
+    PlayerGames { games: Vec<GameState>, next_start_after: Option<u64> },
+    Pgn(String),
+    Match(MatchState),
 }
 
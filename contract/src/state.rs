@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr};
+use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{de::Error, Deserialize, Serialize};
 use secret_toolkit::storage::{Item, Keymap};
@@ -10,9 +10,251 @@ pub struct GameState {
     pub fen: String,          // e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
     pub white: Option<Addr>,
     pub black: Option<Addr>,
-    pub turn: u64,            // block height
+    pub turn: u64,            // Plies played; the FEN decides whose move it is
     pub status: GameStatus,
     pub wager: u128,
+    pub join_deadline_height: Option<u64>, // Pending game can no longer be joined after this block
+    pub spectators: Vec<Addr>,
+    pub white_referrer: Option<Addr>,
+    pub black_referrer: Option<Addr>,
+    pub premove: Option<Premove>, // Queued by the player waiting for their turn
+    // Every move played, in coordinate notation (e.g. "e2e4", "e7e8q"). This grows by one entry
+    // per ply, so long games cost more storage and gas to load and save.
+    pub moves: Vec<String>,
+    pub move_heights: Vec<u64>, // Block each entry of `moves` was played in, always the same length
+    // Positions (see `position_key`) since the last capture or pawn move, for spotting threefold
+    // repetition. Stored rather than recomputed from `moves` so a move doesn't replay the game,
+    // and only back to the last irreversible move because nothing earlier can repeat.
+    pub positions: Vec<String>,
+    pub start_fen: String,    // Position `moves` are played from
+    pub draw_offered_by: Option<Addr>, // Cleared once the offer expires (see draw_offer_plies)
+    pub draw_offered_at: Option<u64>, // `turn` of the latest draw offer, kept after it expires
+    pub takeback_offered_by: Option<Addr>, // Cleared by the next move
+    pub abort_offered_by: Option<Addr>, // Cleared by the next move
+    pub last_move_height: u64,         // Block the side to move's clock started at
+    pub last_activity_height: u64,     // Last block either player did anything in this game
+    pub move_timeout_blocks: Option<u64>, // Blocks a player has to move before the opponent can claim the win
+    pub paid: bool,           // Set once the result has been settled, so it can never pay out twice
+    pub invited: Option<Addr>, // Only this address may join (private games and rematches)
+    pub provisional_colors: bool, // Creator sits as White until the joining block picks colours
+    pub winner: Option<Addr>,  // Set when a decisive game settles; None for draws
+    pub created_height: u64,
+    pub started_height: Option<u64>, // Block the second player joined in
+    pub started_time: Option<u64>,   // Block time of started_height, in seconds since the epoch
+    pub ended_height: Option<u64>,   // Block the result was settled in
+    pub match_id: Option<u64>,       // Set for the rounds of a match, which holds their stakes
+    pub variant: Option<String>,     // "chess960", or None for standard chess
+    pub require_ready: bool,         // Joining leads to Ready rather than straight to Active
+    pub ready: Vec<Addr>,            // Players who have confirmed they're at the board
+}
+
+// A best-of-N series between two players, played one free game at a time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MatchState {
+    pub id: u64,
+    pub best_of: u8,
+    pub wager: u128,          // Staked once by each player for the whole match
+    pub creator: Addr,        // Plays White in odd-numbered rounds
+    pub opponent: Option<Addr>, // None until someone joins
+    pub creator_wins: u8,
+    pub opponent_wins: u8,
+    pub games: Vec<u64>,      // Ids of the rounds so far; the last is the one being played
+    pub winner: Option<Addr>, // Set once either player has won a majority of best_of
+    pub paid: bool,
+}
+
+// What is kept of a settled game once it has been archived
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameResult {
+    pub id: u64,
+    pub white: Option<Addr>,
+    pub black: Option<Addr>,
+    pub status: GameStatus,
+    pub winner: Option<Addr>,
+}
+
+// The original layout of GameState, from before the contract recorded a state version
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameStateV0 {
+    pub id: u64,
+    pub fen: String,
+    pub white: Option<Addr>,
+    pub black: Option<Addr>,
+    pub turn: u64,
+    pub status: GameStatus,
+    pub wager: u128,
+}
+
+// GameState as state version 1 saved it, before move heights, takebacks, aborts, expiring draw
+// offers, provisional colours, matches, variants and the Ready handshake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameStateV1 {
+    pub id: u64,
+    pub fen: String,
+    pub white: Option<Addr>,
+    pub black: Option<Addr>,
+    pub turn: u64,
+    pub status: GameStatus,
+    pub wager: u128,
+    pub join_deadline_height: Option<u64>,
+    pub spectators: Vec<Addr>,
+    pub white_referrer: Option<Addr>,
+    pub black_referrer: Option<Addr>,
+    pub premove: Option<PremoveV1>,
+    pub moves: Vec<String>,
+    pub positions: Vec<String>,
+    pub start_fen: String,
+    pub draw_offered_by: Option<Addr>,
+    pub last_move_height: u64,
+    pub last_activity_height: u64,
+    pub move_timeout_blocks: Option<u64>,
+    pub paid: bool,
+    pub invited: Option<Addr>,
+    pub winner: Option<Addr>,
+    pub created_height: u64,
+    pub started_height: Option<u64>,
+    pub started_time: Option<u64>,
+    pub ended_height: Option<u64>,
+}
+
+// Version 1 premoves kept the promotion as its coordinate-notation letter
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PremoveV1 {
+    pub player: Addr,
+    pub move_from: String,
+    pub move_to: String,
+    pub promotion: Option<String>,
+}
+
+// Config as state version 1 saved it, before the settings from winner_bps onwards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV1 {
+    pub fee_bps: u16,
+    pub fee_collector: Addr,
+    pub referral_bps: u16,
+    pub max_spectators: u32,
+    pub wager_token: Option<TokenContract>,
+    pub owner: Addr,
+    pub paused: bool,
+    pub min_wager: u128,
+    pub max_wager: Option<u128>,
+    pub abandonment_blocks: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Premove {
+    pub player: Addr,
+    pub move_from: String,
+    pub move_to: String,
+    pub promotion: Option<Promotion>,
+}
+
+// Piece a pawn promotes to: "queen", "rook", "bishop" or "knight", or the single letter older
+// clients send
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Promotion {
+    #[serde(alias = "q", alias = "Q")]
+    Queen,
+    #[serde(alias = "r", alias = "R")]
+    Rook,
+    #[serde(alias = "b", alias = "B")]
+    Bishop,
+    #[serde(alias = "n", alias = "N")]
+    Knight,
+}
+
+impl Promotion {
+    // Lowercase letter used in coordinate notation, e.g. the "q" in "e7e8q"
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Promotion::Queen => "q",
+            Promotion::Rook => "r",
+            Promotion::Bishop => "b",
+            Promotion::Knight => "n",
+        }
+    }
+
+    pub fn from_letter(letter: &str) -> Option<Promotion> {
+        match letter {
+            "q" => Some(Promotion::Queen),
+            "r" => Some(Promotion::Rook),
+            "b" => Some(Promotion::Bishop),
+            "n" => Some(Promotion::Knight),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayerProfile {
+    pub name: Option<String>,
+    pub elo: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PlayerStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub games_played: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub fee_bps: u16,         // Platform fee taken from a winner's pot, in basis points
+    pub fee_collector: Addr,
+    pub referral_bps: u16,    // Share of the platform fee paid out to referrers, in basis points
+    pub max_spectators: u32,  // Most spectators a single game will register
+    pub wager_token: Option<TokenContract>, // SNIP-20 wagers are paid in; native uscrt when None
+    pub owner: Addr,
+    pub paused: bool,         // Blocks new games, joins and moves while set
+    pub min_wager: u128,
+    pub max_wager: Option<u128>,
+    pub abandonment_blocks: u64, // Inactivity after which the waiting player can claim the game
+    pub winner_bps: u16,      // Winner's share of the pot after fees; the collector gets the rest
+    pub max_active_games_per_player: Option<u32>, // No limit when None
+    pub archive_finished_games: bool, // Replace settled games with their GameResult
+    pub abort_plies: u32,     // Players can agree to abort only while fewer plies have been played
+    pub max_plies: Option<u32>, // A game still going at this many plies is drawn; no cap when None
+    pub draw_offer_plies: u32, // Plies a draw offer survives; 0 means the next move ends it
+    pub accrue_fees: bool,    // The collector is this contract, which holds fees for the owner
+}
+
+// Which code a deployed instance is running, in the style of cw2
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractVersion {
+    pub contract: String, // Crate name
+    pub version: String,  // Crate version, e.g. "0.1.0"
+}
+
+// Running totals kept up to date by the handlers, so the Stats query never has to scan storage
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Counters {
+    pub status_counts: Vec<u64>, // Games in each status, indexed by the status value minus one
+    pub escrowed: u128,          // Stakes held for games and matches that haven't settled yet
+    pub players: u64,            // Distinct addresses that have had a seat in a game
+}
+
+impl Counters {
+    // Moves one game between statuses; `from` is None for a new game, `to` for a removed one.
+    // Games from before the counters existed were never counted, so nothing goes below zero.
+    pub fn move_game(&mut self, from: Option<GameStatus>, to: Option<GameStatus>) {
+        self.status_counts.resize(GameStatus::ALL.len(), 0);
+        if let Some(from) = from {
+            let count = &mut self.status_counts[from as usize - 1];
+            *count = count.saturating_sub(1);
+        }
+        if let Some(to) = to {
+            self.status_counts[to as usize - 1] += 1;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenContract {
+    pub address: Addr,
+    pub code_hash: String,
 }
 
 
@@ -25,11 +267,119 @@ pub enum GameStatus {
     BlackWins,
     WhiteResigned,
     BlackResigned,
+    DrawFiftyMove,            // Fifty-move rule claimed, or seventy-five moves reached
+    DrawAgreed,               // One player offered a draw and the other accepted
+    DrawRepetition,           // Threefold repetition claimed, or fivefold reached
+    DrawInsufficientMaterial, // Neither side has enough pieces left to checkmate
+    Aborted,                  // Called off by both players early on; refunded and never rated
+    DrawMaxMoves,             // Reached the contract's max_plies without a result
+    Ready,                    // Both seats taken, waiting for both players to confirm the start
+}
+
+impl GameStatus {
+    pub const ALL: [GameStatus; 14] = [
+        GameStatus::Pending,
+        GameStatus::Active,
+        GameStatus::Stalemate,
+        GameStatus::WhiteWins,
+        GameStatus::BlackWins,
+        GameStatus::WhiteResigned,
+        GameStatus::BlackResigned,
+        GameStatus::DrawFiftyMove,
+        GameStatus::DrawAgreed,
+        GameStatus::DrawRepetition,
+        GameStatus::DrawInsufficientMaterial,
+        GameStatus::Aborted,
+        GameStatus::DrawMaxMoves,
+        GameStatus::Ready,
+    ];
+
+    // Stable name for clients, so they don't have to mirror the integer encoding
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameStatus::Pending => "pending",
+            GameStatus::Active => "active",
+            GameStatus::Stalemate => "stalemate",
+            GameStatus::WhiteWins => "white_wins",
+            GameStatus::BlackWins => "black_wins",
+            GameStatus::WhiteResigned => "white_resigned",
+            GameStatus::BlackResigned => "black_resigned",
+            GameStatus::DrawFiftyMove => "draw_fifty_move",
+            GameStatus::DrawAgreed => "draw_agreed",
+            GameStatus::DrawRepetition => "draw_repetition",
+            GameStatus::DrawInsufficientMaterial => "draw_insufficient_material",
+            GameStatus::Aborted => "aborted",
+            GameStatus::DrawMaxMoves => "draw_max_moves",
+            GameStatus::Ready => "ready",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, GameStatus::Pending | GameStatus::Ready | GameStatus::Active)
+    }
+
+    // Some(true) if White won, Some(false) if Black won, None for draws and unfinished games
+    pub fn white_won(&self) -> Option<bool> {
+        match self {
+            GameStatus::WhiteWins | GameStatus::BlackResigned => Some(true),
+            GameStatus::BlackWins | GameStatus::WhiteResigned => Some(false),
+            _ => None,
+        }
+    }
+
+    // Draws refund each player their own wager
+    pub fn is_draw(&self) -> bool {
+        matches!(
+            self,
+            GameStatus::Stalemate
+                | GameStatus::DrawFiftyMove
+                | GameStatus::DrawAgreed
+                | GameStatus::DrawRepetition
+                | GameStatus::DrawInsufficientMaterial
+                | GameStatus::DrawMaxMoves
+        )
+    }
+
+    // Draws and aborted games hand each player back their own wager
+    pub fn refunds_wagers(&self) -> bool {
+        self.is_draw() || *self == GameStatus::Aborted
+    }
 }
 
 
 pub const GAMES: Keymap<u64, GameState> = Keymap::new(b"games");
+// Same storage as GAMES, read with the layout games were saved in before versioning
+pub const GAMES_V0: Keymap<u64, GameStateV0> = Keymap::new(b"games");
+// ...and with the version 1 layout
+pub const GAMES_V1: Keymap<u64, GameStateV1> = Keymap::new(b"games");
+// Layout version of everything in storage; missing means version 0
+pub const STATE_VERSION: Item<u16> = Item::new(b"state_version");
 pub const NEXT_GAME_ID: Item<u64> = Item::new(b"next_game_id");
+// Written at instantiate and on every migrate
+pub const CONTRACT_INFO: Item<ContractVersion> = Item::new(b"contract_info");
+pub const CONFIG: Item<Config> = Item::new(b"config");
+// Same storage as CONFIG, in the version 1 layout
+pub const CONFIG_V1: Item<ConfigV1> = Item::new(b"config");
+// Sum of the gross pots of every settled game
+pub const TOTAL_VOLUME: Item<Uint128> = Item::new(b"total_volume");
+// Fees held by the contract for the owner to withdraw, when the contract is its own collector
+pub const ACCRUED_FEES: Item<Uint128> = Item::new(b"accrued_fees");
+// Reverse index of spectator address -> ids of the games they are watching
+pub const SPECTATING: Keymap<Addr, Vec<u64>> = Keymap::new(b"spectating");
+pub const PLAYERS: Keymap<Addr, PlayerProfile> = Keymap::new(b"players");
+// Index of player address -> ids of the games they have a seat in, kept in ascending order
+pub const PLAYER_GAMES: Keymap<Addr, Vec<u64>> = Keymap::new(b"player_games");
+// Results of settled games, recorded alongside the payout so each game counts once
+pub const PLAYER_STATS: Keymap<Addr, PlayerStats> = Keymap::new(b"player_stats");
+// Results of games removed from GAMES after settling, when archiving is enabled
+pub const ARCHIVED_GAMES: Keymap<u64, GameResult> = Keymap::new(b"archived_games");
+pub const MATCHES: Keymap<u64, MatchState> = Keymap::new(b"matches");
+pub const NEXT_MATCH_ID: Item<u64> = Item::new(b"next_match_id");
+pub const COUNTERS: Item<Counters> = Item::new(b"counters");
+// Number of pending or active games each player has a seat in
+pub const ACTIVE_GAMES: Keymap<Addr, u32> = Keymap::new(b"active_games");
+// Lobby index of wager -> ids of the pending games anyone may join at it, in ascending order
+pub const OPEN_GAMES: Keymap<u128, Vec<u64>> = Keymap::new(b"open_games");
 
 // Enums need to be serialized manually due to floating point operations in the serde serialization
 impl Serialize for GameStatus {
@@ -53,7 +403,14 @@ impl<'de> Deserialize<'de> for GameStatus {
             4  => Ok(GameStatus::WhiteWins),
             5  => Ok(GameStatus::BlackWins),
             6  => Ok(GameStatus::WhiteResigned),
-            7 => Ok(GameStatus::BlackResigned),
+            7  => Ok(GameStatus::BlackResigned),
+            8  => Ok(GameStatus::DrawFiftyMove),
+            9  => Ok(GameStatus::DrawAgreed),
+            10 => Ok(GameStatus::DrawRepetition),
+            11 => Ok(GameStatus::DrawInsufficientMaterial),
+            12 => Ok(GameStatus::Aborted),
+            13 => Ok(GameStatus::DrawMaxMoves),
+            14 => Ok(GameStatus::Ready),
             _ => Err(Error::custom("Invalid GameStatus value")),
         }
     }
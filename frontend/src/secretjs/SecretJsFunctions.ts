@@ -110,7 +110,7 @@ const SecretJsFunctions = () => {
             const response = await secretJs.query.compute.queryContract(msg);
             console.log(response);
             // @ts-ignore
-            return response.game_state as GameState;
+            return response.game_state.game as GameState;
         } catch (error) {
             throw new QueryError("Failed to fetch game status: " + error);
         }
@@ -118,19 +118,26 @@ const SecretJsFunctions = () => {
 
     const listGames = async (): Promise<GameState[]> => {
         if (!secretJs || !secretAddress) throw new WalletError("no wallet connected");
-        const msg = {
-            contract_address: contractAddress,
-            code_hash: contractCodeHash,
-            query: {
-                list_games: {}
-            }
-        };
 
         try {
-            const response = await secretJs.query.compute.queryContract(msg);
-            console.log(response);
-            // @ts-ignore
-            return response.all_games as GameState[];
+            // Follow the pagination cursor until every game has been fetched
+            const games: GameState[] = [];
+            let startAfter: number | null = null;
+            do {
+                const response = await secretJs.query.compute.queryContract({
+                    contract_address: contractAddress,
+                    code_hash: contractCodeHash,
+                    query: {
+                        list_games: { start_after: startAfter }
+                    }
+                });
+                console.log(response);
+                // @ts-ignore
+                games.push(...(response.all_games.games as GameState[]));
+                // @ts-ignore
+                startAfter = response.all_games.next_start_after;
+            } while (startAfter !== null && startAfter !== undefined);
+            return games;
         } catch (error) {
             throw new QueryError("Failed to fetch game list: " + error);
         }